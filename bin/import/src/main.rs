@@ -3,7 +3,7 @@ use chrono_tz::Europe::Amsterdam;
 use indicatif::ProgressBar;
 use serde::Deserialize;
 use std::{collections::HashMap, error::Error};
-use tables::{bits, subgifts, user, Orm, OrmBase};
+use tables::{bits, query::Insert, subgifts, user, Orm, OrmBase};
 
 #[derive(Debug, Clone, Deserialize)]
 struct User {
@@ -101,26 +101,29 @@ fn get_json_users() -> Result<Vec<JsonUser>, Box<dyn Error>> {
     Ok(parsed.data)
 }
 
-fn string_time_to_iso(raw: String) -> String {
+fn string_time_to_utc(raw: String) -> DateTime<Utc> {
     let fmt = "%Y-%m-%d %H:%M";
     let dt = NaiveDateTime::parse_from_str(&raw, fmt).expect("Failed to parse time");
     let amsterdam_time = Amsterdam.from_local_datetime(&dt).unwrap();
-    let utc: DateTime<Utc> = amsterdam_time.with_timezone(&Utc);
 
-    format!("{}", utc.format("%+")).to_string()
+    amsterdam_time.with_timezone(&Utc)
+}
+
+fn followed_at(follower: &JsonUser) -> DateTime<Utc> {
+    tables::timestamp::parse(&follower.followed_at).expect("Failed to parse followed_at")
 }
 
 impl From<User> for user::User {
     fn from(value: User) -> Self {
         let mut bld = user::User::builder(value.display_name, value.twitch_id)
-            .created_at(string_time_to_iso(value.created_at));
+            .created_at(string_time_to_utc(value.created_at));
 
         if let Some(follow_since) = value.follower_since {
-            bld = bld.clone().follow(string_time_to_iso(follow_since));
+            bld = bld.clone().follow(string_time_to_utc(follow_since));
         }
 
         if let (Some(sub_since), Some(tier)) = (value.subscriber_since, value.subscription_tier) {
-            bld = bld.clone().subscribe(string_time_to_iso(sub_since));
+            bld = bld.clone().subscribe(string_time_to_utc(sub_since));
             bld = bld.clone().tier(tier);
         }
 
@@ -133,13 +136,14 @@ impl From<User> for user::User {
 
 impl From<Bit> for bits::Bit {
     fn from(value: Bit) -> Self {
-        let created_at = string_time_to_iso(value.created);
+        let created_at = string_time_to_utc(value.created);
         Self {
             id: 0,
             user_id: value.user_id,
             number: value.number,
             created_at,
             message: value.message,
+            stream_id: None,
         }
     }
 }
@@ -151,7 +155,8 @@ impl From<Subgift> for subgifts::Subgift {
             user_id: value.user_id,
             number: value.number,
             tier: value.tier,
-            created_at: string_time_to_iso(value.created_at),
+            created_at: string_time_to_utc(value.created_at),
+            stream_id: None,
         }
     }
 }
@@ -189,10 +194,10 @@ async fn import_from_csvs(conn: &libsql::Connection) -> Result<(), Box<dyn Error
 
     let mut table_subgifts: Vec<subgifts::Subgift> =
         subgifts.into_iter().map(|u| u.into()).collect();
-    table_subgifts.sort_by_key(|r| r.created_at.clone());
+    table_subgifts.sort_by_key(|r| r.created_at);
 
     let mut table_bits: Vec<bits::Bit> = bits.into_iter().map(|u| u.into()).collect();
-    table_bits.sort_by_key(|r| r.created_at.clone());
+    table_bits.sort_by_key(|r| r.created_at);
 
     let mut user_map = HashMap::<u64, u64>::new();
     let pb = ProgressBar::new(table_users.len() as u64);
@@ -216,33 +221,12 @@ async fn import_from_csvs(conn: &libsql::Connection) -> Result<(), Box<dyn Error
             }
         }
 
-        let mut columns: Vec<String> = vec![
-            "number".to_string(),
-            "tier".to_string(),
-            "created_at".to_string(),
-        ];
-        let mut replacements = vec![
-            subgift.number.to_string(),
-            subgift.tier.clone(),
-            subgift.created_at.clone(),
-        ];
-
-        if let Some(id) = subgift.user_id {
-            columns.push("user_id".to_string());
-            replacements.push(id.to_string());
-        }
+        let (query, params) = Insert::model(subgift)
+            .value("created_at", subgift.created_at)
+            .returning(None)
+            .build();
 
-        let query = format!(
-            "insert into subgifts (
-                {}
-            ) values (
-                {}
-            )",
-            columns.join(", "),
-            Orm::<Subgift>::placeholders(columns.len()),
-        );
-
-        let _ = Orm::<()>::query(conn, &query, replacements)
+        let _ = Orm::<()>::query(conn, &query, params)
             .await
             .expect("Failed to insert subgift");
 
@@ -259,30 +243,12 @@ async fn import_from_csvs(conn: &libsql::Connection) -> Result<(), Box<dyn Error
             }
         }
 
-        let mut columns: Vec<String> = vec!["number".to_string(), "created_at".to_string()];
-        let mut replacements = vec![bit.number.to_string(), bit.created_at.clone()];
-
-        if let Some(id) = bit.user_id {
-            columns.push("user_id".to_string());
-            replacements.push(id.to_string());
-        }
-
-        if let Some(message) = &bit.message {
-            columns.push("message".to_string());
-            replacements.push(message.to_string());
-        }
+        let (query, params) = Insert::model(bit)
+            .value("created_at", bit.created_at)
+            .returning(None)
+            .build();
 
-        let query = format!(
-            "insert into bits (
-                {}
-            ) values (
-                {}
-            )",
-            columns.join(", "),
-            Orm::<Subgift>::placeholders(columns.len()),
-        );
-
-        let _ = Orm::<()>::query(conn, &query, replacements)
+        let _ = Orm::<()>::query(conn, &query, params)
             .await
             .expect("Failed to insert bit");
 
@@ -310,7 +276,7 @@ async fn import_followers_json(conn: &libsql::Connection) -> Result<(), Box<dyn
 
         match follower {
             None => user.follower_since = None,
-            Some(follower) => user.follower_since = Some(follower.followed_at),
+            Some(follower) => user.follower_since = Some(followed_at(&follower)),
         }
 
         user.update(conn).await.expect("Failed to update user");
@@ -333,12 +299,12 @@ async fn import_followers_json(conn: &libsql::Connection) -> Result<(), Box<dyn
         match user {
             None => {
                 let new_user = user::User::builder(follower.clone().user_name, twitch_id)
-                    .follow(follower.clone().followed_at)
+                    .follow(followed_at(follower))
                     .build();
                 new_user.create(conn).await.expect("Failed to create user");
             }
             Some(mut user) => {
-                user.follower_since = Some(follower.clone().followed_at);
+                user.follower_since = Some(followed_at(follower));
                 user.update(conn).await.expect("Failed to update user");
             }
         }
@@ -0,0 +1,354 @@
+//! Derives for the `tables` ORM.
+//!
+//! ```ignore
+//! #[derive(Debug, Clone, Deserialize, Serialize, Model, OrmBase)]
+//! #[orm(table = "bits", validate)]
+//! pub struct Bit {
+//!     pub id: u64,
+//!     pub number: u32,
+//!     pub message: Option<String>,
+//!     #[serde(with = "tables::timestamp")]
+//!     pub created_at: DateTime<Utc>,
+//! }
+//! ```
+//!
+//! `id`, `created_at`, `updated_at` and `deleted_at` are managed by the
+//! generated statements, every other field is a column unless marked
+//! `#[orm(skip)]`. `Option` fields are only written when set. A `deleted_at`
+//! field turns `delete` into a soft delete and hides deleted rows from `get`
+//! and `update`. `validate` calls `self.validate()` before `create` and
+//! `update`. `updated_at` is set to now on insert, or to `created_at` when
+//! marked `#[orm(from_created_at)]`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr, Type};
+
+const MANAGED: [&str; 4] = ["id", "created_at", "updated_at", "deleted_at"];
+
+struct Table {
+    ident: Ident,
+    name: String,
+    validate: bool,
+    columns: Vec<Column>,
+    created_at: bool,
+    /// Whether a new row starts with its `created_at` instead of now.
+    updated_at: Option<bool>,
+    deleted_at: bool,
+}
+
+struct Column {
+    ident: Ident,
+    optional: bool,
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    skip: bool,
+    from_created_at: bool,
+}
+
+impl FieldAttrs {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut attrs = Self::default();
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("orm"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    attrs.skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("from_created_at") {
+                    attrs.from_created_at = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown orm field attribute"))
+                }
+            })?;
+        }
+
+        Ok(attrs)
+    }
+}
+
+impl Table {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut name = None;
+        let mut validate = false;
+
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("orm"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("table") {
+                    let table: LitStr = meta.value()?.parse()?;
+                    name = Some(table.value());
+                    Ok(())
+                } else if meta.path.is_ident("validate") {
+                    validate = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown orm attribute"))
+                }
+            })?;
+        }
+
+        let name = name.ok_or_else(|| {
+            syn::Error::new_spanned(&input.ident, "missing #[orm(table = \"...\")]")
+        })?;
+
+        let fields = match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(fields) => &fields.named,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &input.ident,
+                        "tables models need named fields",
+                    ))
+                }
+            },
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "tables models must be structs",
+                ))
+            }
+        };
+
+        let mut table = Self {
+            ident: input.ident.clone(),
+            name,
+            validate,
+            columns: vec![],
+            created_at: false,
+            updated_at: None,
+            deleted_at: false,
+        };
+
+        for field in fields {
+            let ident = field.ident.clone().expect("named field");
+            let attrs = FieldAttrs::parse(field)?;
+            match ident.to_string().as_str() {
+                "created_at" => table.created_at = true,
+                "updated_at" => table.updated_at = Some(attrs.from_created_at),
+                "deleted_at" => table.deleted_at = true,
+                _ if attrs.from_created_at => {
+                    return Err(syn::Error::new_spanned(
+                        &ident,
+                        "from_created_at only applies to updated_at",
+                    ))
+                }
+                _ => {}
+            }
+
+            if MANAGED.contains(&ident.to_string().as_str()) || attrs.skip {
+                continue;
+            }
+
+            table.columns.push(Column {
+                ident,
+                optional: is_option(&field.ty),
+            });
+        }
+
+        if !fields
+            .iter()
+            .any(|field| field.ident.as_ref().is_some_and(|ident| ident == "id"))
+        {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "tables models need an `id` field",
+            ));
+        }
+
+        Ok(table)
+    }
+
+    /// Used in error messages, e.g. `No user created`.
+    fn label(&self) -> String {
+        self.ident.to_string().to_lowercase()
+    }
+}
+
+fn model(table: &Table) -> TokenStream2 {
+    let ident = &table.ident;
+    let name = &table.name;
+    let columns = table.columns.iter().map(|column| {
+        let field = &column.ident;
+        let name = field.to_string();
+        if column.optional {
+            quote! { ::tables::query::Column::optional(#name, &self.#field) }
+        } else {
+            quote! { ::tables::query::Column::new(#name, &self.#field) }
+        }
+    });
+
+    quote! {
+        impl ::tables::query::Model for #ident {
+            const TABLE: &'static str = #name;
+
+            fn columns(&self) -> Vec<::tables::query::Column> {
+                vec![#(#columns),*]
+            }
+        }
+    }
+}
+
+fn orm_base(table: &Table) -> TokenStream2 {
+    let ident = &table.ident;
+    let label = table.label();
+
+    let validate = table.validate.then(|| quote! { self.validate()?; });
+    // an empty created_at falls back to now
+    let created_at = table
+        .created_at
+        .then(|| quote! { .timestamp("created_at", &self.created_at) });
+    let updated_at_on_create = match table.updated_at {
+        Some(true) if table.created_at => {
+            Some(quote! { .timestamp("updated_at", &self.created_at) })
+        }
+        Some(_) => Some(quote! { .now("updated_at") }),
+        None => None,
+    };
+    let updated_at = table
+        .updated_at
+        .is_some()
+        .then(|| quote! { .now("updated_at") });
+    let not_deleted = table
+        .deleted_at
+        .then(|| quote! { .filter_sql("deleted_at is null") });
+
+    let get = format!(
+        "select * from {} where id = ?1{} limit 1",
+        table.name,
+        if table.deleted_at {
+            " and deleted_at is null"
+        } else {
+            ""
+        }
+    );
+    let delete = if table.deleted_at {
+        quote! {
+            ::tables::query::Update::table(<Self as ::tables::query::Model>::TABLE)
+                .now("deleted_at")
+                .filter("id", self.id)
+                .filter_sql("deleted_at is null")
+                .build()
+        }
+    } else {
+        let query = format!("delete from {} where id = ?1", table.name);
+        quote! { (#query.to_string(), ::tables::params![self.id]) }
+    };
+
+    quote! {
+        impl ::tables::OrmBase<#ident> for #ident {
+            async fn create(
+                &self,
+                conn: &::libsql::Connection,
+            ) -> Result<u64, ::tables::OrmError> {
+                #validate
+
+                let (query, params) = ::tables::query::Insert::model(self)
+                    #created_at
+                    #updated_at_on_create
+                    .build();
+
+                let rows = ::tables::Orm::<::tables::RowId>::query(conn, &query, params).await?;
+
+                match rows.first() {
+                    None => Err(::tables::OrmError::NoChange(format!("No {} created", #label))),
+                    Some(row) => Ok(row.id),
+                }
+            }
+
+            async fn get(
+                conn: &::libsql::Connection,
+                id: u64,
+            ) -> Result<Option<Self>, ::tables::OrmError> {
+                let rows = ::tables::Orm::<Self>::query(conn, #get, ::tables::params![id]).await?;
+
+                Ok(rows.into_iter().next())
+            }
+
+            async fn update(&mut self, conn: &::libsql::Connection) -> Result<(), ::tables::OrmError> {
+                if <Self as ::tables::OrmBase<Self>>::get(conn, self.id).await?.is_none() {
+                    return Err(::tables::OrmError::NotFound(
+                        format!("update {}", #label),
+                        Some(self.id),
+                    ));
+                }
+
+                #validate
+
+                let (query, params) = ::tables::query::Update::model(self)
+                    #updated_at
+                    .filter("id", self.id)
+                    #not_deleted
+                    .build();
+
+                ::tables::Orm::<Self>::execute(conn, &query, params).await?;
+
+                if let Some(stored) = <Self as ::tables::OrmBase<Self>>::get(conn, self.id).await? {
+                    *self = stored;
+                }
+
+                Ok(())
+            }
+
+            async fn delete(&self, conn: &::libsql::Connection) -> Result<(), ::tables::OrmError> {
+                if <Self as ::tables::OrmBase<Self>>::get(conn, self.id).await?.is_none() {
+                    return Err(::tables::OrmError::NotFound(
+                        format!("delete {}", #label),
+                        Some(self.id),
+                    ));
+                }
+
+                let (query, params) = #delete;
+
+                ::tables::Orm::<Self>::execute(conn, &query, params).await?;
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Implements `tables::query::Model`: the table name and the column list
+/// used by the insert and update builders.
+#[proc_macro_derive(Model, attributes(orm))]
+pub fn derive_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match Table::parse(&input) {
+        Ok(table) => model(&table).into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Implements `tables::OrmBase` on top of the `Model` impl.
+#[proc_macro_derive(OrmBase, attributes(orm))]
+pub fn derive_orm_base(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match Table::parse(&input) {
+        Ok(table) => orm_base(&table).into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
@@ -1,14 +1,18 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
-use crate::{add_if_present, user::User, Orm, OrmBase, OrmError, RowId, SQL_NOW_UTC_ISO};
+use crate::{params, query::Model, Orm, OrmBase, OrmError};
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Model, OrmBase)]
+#[orm(table = "bits", validate)]
 pub struct Bit {
     pub id: u64,
     pub user_id: Option<u64>,
     pub number: u32,
     pub message: Option<String>,
-    pub created_at: String,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime<Utc>,
+    pub stream_id: Option<u64>,
 }
 
 impl Default for Bit {
@@ -25,7 +29,8 @@ impl Bit {
             user_id: None,
             number: 0,
             message: None,
-            created_at: String::new(),
+            created_at: Utc::now(),
+            stream_id: None,
         }
     }
 
@@ -36,7 +41,8 @@ impl Bit {
             user_id: Some(user_id),
             number,
             message,
-            created_at: String::new(),
+            created_at: Utc::now(),
+            stream_id: None,
         }
     }
 
@@ -47,53 +53,42 @@ impl Bit {
             user_id: None,
             number,
             message,
-            created_at: String::new(),
+            created_at: Utc::now(),
+            stream_id: None,
         }
     }
 
     #[allow(dead_code)]
-    pub async fn create(&self, conn: &libsql::Connection) -> Result<u64, OrmError> {
+    fn validate(&self) -> Result<(), OrmError> {
         if self.number == 0 {
             return Err(OrmError::BadInput("Bits number cannot be 0".to_string()));
         }
 
-        let mut columns = vec!["number"];
-        let mut replacements = vec![self.number.to_string()];
-
-        if let Some(id) = self.user_id {
-            User::get(conn, id).await?;
-            columns.push("user_id");
-            replacements.push(id.to_string());
-        }
-
-        add_if_present!(columns, replacements, self, message);
-
-        let query = format!(
-            "insert into bits (
-                {}, created_at
-            ) values (
-                {}, {}
-            ) returning id",
-            columns.join(", "),
-            Orm::<Bit>::placeholders(columns.len()),
-            SQL_NOW_UTC_ISO,
-        );
-
-        let rows = Orm::<RowId>::query(conn, &query, replacements).await?;
+        Ok(())
+    }
 
-        match rows.first() {
-            None => Err(OrmError::NoChange("No bit created".to_string())),
-            Some(row) => Ok(row.id),
-        }
+    #[allow(dead_code)]
+    pub async fn list(
+        conn: &libsql::Connection,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Self>, OrmError> {
+        let query = "select * from bits
+            order by id desc
+            limit ?1 offset ?2
+        ";
+        let params = params![limit, offset];
+
+        Orm::<Bit>::query(conn, query, params).await
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{user::User, OrmBase, CHRONO_UTC_ISO_FMT};
+    use crate::{user::User, OrmBase};
 
     use super::*;
-    use chrono::NaiveDateTime;
+    use chrono::TimeZone;
     use libsql::{de, Builder, Connection};
     use rand::{distributions::Alphanumeric, Rng};
     use tracing_test::traced_test;
@@ -200,7 +195,94 @@ mod tests {
         assert_eq!(bit_st.user_id, Some(1));
         assert_eq!(bit_st.number, 1);
         assert_eq!(bit_st.message, Some("message".to_string()));
-        let created_at = NaiveDateTime::parse_from_str(&bit_st.created_at, CHRONO_UTC_ISO_FMT);
-        assert!(created_at.is_ok());
+        assert!(bit_st.created_at <= Utc::now());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_created_at() {
+        // arrange
+        let conn = conn(true).await;
+        let mut bit = Bit::from(1, 100, None);
+        bit.created_at = Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap();
+
+        // act
+        let res = bit.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        let mut rows = conn
+            .query(
+                "select created_at from bits where id = ?1 limit 1",
+                [res.unwrap()],
+            )
+            .await
+            .unwrap();
+        let row = rows.next().await.unwrap().unwrap();
+        assert_eq!(
+            row.get::<String>(0).unwrap(),
+            "2025-02-10T20:00:00.000Z".to_string()
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn list() {
+        // arrange
+        let conn = conn(true).await;
+        Bit::from(1, 1, None).create(&conn).await.unwrap();
+        Bit::from_anonymous(2, None).create(&conn).await.unwrap();
+
+        // act
+        let res = Bit::list(&conn, 10, 0).await;
+
+        // assert
+        assert!(res.is_ok());
+        let bits = res.unwrap();
+        assert_eq!(bits.len(), 2);
+        assert_eq!(bits[0].number, 2);
+        assert_eq!(bits[0].user_id, None);
+        assert_eq!(bits[1].number, 1);
+        assert_eq!(bits[1].user_id, Some(1));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn update() {
+        // arrange
+        let conn = conn(true).await;
+        let id = Bit::from(1, 100, None).create(&conn).await.unwrap();
+        let mut bit = Bit::get(&conn, id).await.unwrap().unwrap();
+        bit.message = Some("cheer100".to_string());
+
+        // act
+        let res = bit.update(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        let bit_st = Bit::get(&conn, id).await.unwrap().unwrap();
+        assert_eq!(bit_st.message, Some("cheer100".to_string()));
+        assert_eq!(bit, bit_st);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn delete_not_found() {
+        // arrange
+        let conn = conn(true).await;
+        let bit = Bit {
+            id: 42,
+            ..Bit::from(1, 100, None)
+        };
+
+        // act
+        let res = bit.delete(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::NotFound("delete bit".to_string(), Some(42))
+        );
     }
 }
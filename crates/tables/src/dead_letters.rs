@@ -0,0 +1,203 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    params,
+    query::{Insert, Model},
+    Orm, OrmError, RowId,
+};
+
+/// A notification a sink gave up delivering, kept so it can be inspected or
+/// replayed by hand.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Model)]
+#[orm(table = "dead_letters")]
+pub struct DeadLetter {
+    pub id: u64,
+    pub sink: String,
+    pub target: String,
+    pub payload: String,
+    pub attempts: u32,
+    pub error: Option<String>,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Default for DeadLetter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeadLetter {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self {
+            id: 0,
+            sink: String::new(),
+            target: String::new(),
+            payload: String::new(),
+            attempts: 0,
+            error: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from(sink: String, target: String, payload: String, attempts: u32) -> Self {
+        Self {
+            sink,
+            target,
+            payload,
+            attempts,
+            ..Self::new()
+        }
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self) -> Result<(), OrmError> {
+        if self.sink.is_empty() || self.target.is_empty() {
+            return Err(OrmError::BadInput(
+                "Dead letter requires a sink and a target".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn create(&self, conn: &libsql::Connection) -> Result<u64, OrmError> {
+        self.validate()?;
+
+        let (query, params) = Insert::model(self).now("created_at").build();
+
+        let rows = Orm::<RowId>::query(conn, &query, params).await?;
+
+        match rows.first() {
+            None => Err(OrmError::NoChange("No dead letter created".to_string())),
+            Some(row) => Ok(row.id),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn list(
+        conn: &libsql::Connection,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Self>, OrmError> {
+        let query = "select * from dead_letters
+            order by id desc
+            limit ?1 offset ?2
+        ";
+        let params = params![limit, offset];
+
+        Orm::<DeadLetter>::query(conn, query, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libsql::{Builder, Connection};
+    use rand::{distributions::Alphanumeric, Rng};
+    use tracing_test::traced_test;
+
+    async fn conn() -> Connection {
+        let rand: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        let db_name = format!("test_{}.sqlite", rand.to_lowercase());
+        std::fs::copy("tests.sqlite", &db_name).unwrap();
+
+        let db = Builder::new_local(&db_name).build().await.unwrap();
+        let conn = db.connect().unwrap();
+
+        println!("Running test on {}", db_name);
+
+        conn
+    }
+
+    fn dead_letter(target: &str) -> DeadLetter {
+        DeadLetter::from(
+            "webhook".to_string(),
+            target.to_string(),
+            "{\"type\":\"follow\"}".to_string(),
+            5,
+        )
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors() {
+        // arrange
+        let conn = conn().await;
+        let dead_letter = dead_letter("");
+
+        // act
+        let res = dead_letter.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Dead letter requires a sink and a target".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create() {
+        // arrange
+        let conn = conn().await;
+        let mut dead_letter = dead_letter("https://example.com/hook");
+        dead_letter.error = Some("500 Internal Server Error".to_string());
+
+        // act
+        let res = dead_letter.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 1);
+
+        let dead_letters = DeadLetter::list(&conn, 10, 0).await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].sink, "webhook".to_string());
+        assert_eq!(
+            dead_letters[0].target,
+            "https://example.com/hook".to_string()
+        );
+        assert_eq!(dead_letters[0].payload, "{\"type\":\"follow\"}".to_string());
+        assert_eq!(dead_letters[0].attempts, 5);
+        assert_eq!(
+            dead_letters[0].error,
+            Some("500 Internal Server Error".to_string())
+        );
+        assert!(dead_letters[0].created_at <= Utc::now());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn list() {
+        // arrange
+        let conn = conn().await;
+        dead_letter("https://a.example.com")
+            .create(&conn)
+            .await
+            .unwrap();
+        dead_letter("https://b.example.com")
+            .create(&conn)
+            .await
+            .unwrap();
+
+        // act
+        let res = DeadLetter::list(&conn, 10, 0).await;
+
+        // assert
+        assert!(res.is_ok());
+        let dead_letters = res.unwrap();
+        assert_eq!(dead_letters.len(), 2);
+        assert_eq!(dead_letters[0].target, "https://b.example.com".to_string());
+        assert_eq!(dead_letters[0].error, None);
+    }
+}
@@ -0,0 +1,442 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    params,
+    query::{Insert, Model},
+    Orm, OrmError, RowId,
+};
+
+/// Append-only journal of every EventSub notification, kept verbatim so the
+/// derived tables can be audited and rebuilt. Rows carrying a normalized
+/// `payload` are also what live clients replay from.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Model)]
+#[orm(table = "events")]
+pub struct Event {
+    pub id: u64,
+    pub message_id: Option<String>,
+    pub subscription_type: Option<String>,
+    pub subscription_version: Option<String>,
+    pub message_timestamp: Option<String>,
+    pub raw: Option<String>,
+    pub user_id: Option<u64>,
+    pub kind: Option<String>,
+    pub payload: Option<String>,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime<Utc>,
+    pub stream_id: Option<u64>,
+}
+
+impl Default for Event {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Event {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self {
+            id: 0,
+            message_id: None,
+            subscription_type: None,
+            subscription_version: None,
+            message_timestamp: None,
+            raw: None,
+            user_id: None,
+            kind: None,
+            payload: None,
+            created_at: Utc::now(),
+            stream_id: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn notification(
+        message_id: String,
+        subscription_type: String,
+        subscription_version: String,
+        message_timestamp: String,
+        raw: String,
+    ) -> Self {
+        Self {
+            message_id: Some(message_id),
+            subscription_type: Some(subscription_type),
+            subscription_version: Some(subscription_version),
+            message_timestamp: Some(message_timestamp),
+            raw: Some(raw),
+            ..Self::new()
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn live(mut self, kind: String, payload: String) -> Self {
+        self.kind = Some(kind);
+        self.payload = Some(payload);
+        self
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self) -> Result<(), OrmError> {
+        if self.raw.is_none() && self.payload.is_none() {
+            return Err(OrmError::BadInput(
+                "Event requires a raw notification or a payload".to_string(),
+            ));
+        }
+
+        if self.kind.is_some() != self.payload.is_some() {
+            return Err(OrmError::BadInput(
+                "Event kind and payload must be set together".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Fails with `OrmError::NoChange` when a notification with the same
+    /// message id was already recorded.
+    #[allow(dead_code)]
+    pub async fn create(&self, conn: &libsql::Connection) -> Result<u64, OrmError> {
+        self.validate()?;
+
+        let (query, params) = Insert::model(self)
+            .timestamp("created_at", self.created_at)
+            .on_conflict("(message_id) do nothing")
+            .build();
+
+        let rows = Orm::<RowId>::query(conn, &query, params).await?;
+
+        match rows.first() {
+            None => Err(OrmError::NoChange("No event created".to_string())),
+            Some(row) => Ok(row.id),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn set_user_id(
+        conn: &libsql::Connection,
+        id: u64,
+        user_id: u64,
+    ) -> Result<(), OrmError> {
+        let query = "update events set user_id = ?1 where id = ?2";
+        let params = params![user_id, id];
+
+        let affected = Orm::<Event>::execute(conn, query, params).await?;
+
+        if affected == 0 {
+            return Err(OrmError::NotFound("event".to_string(), Some(id)));
+        }
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn set_stream_id(
+        conn: &libsql::Connection,
+        id: u64,
+        stream_id: u64,
+    ) -> Result<(), OrmError> {
+        let query = "update events set stream_id = ?1 where id = ?2";
+        let params = params![stream_id, id];
+
+        let affected = Orm::<Event>::execute(conn, query, params).await?;
+
+        if affected == 0 {
+            return Err(OrmError::NotFound("event".to_string(), Some(id)));
+        }
+
+        Ok(())
+    }
+
+    /// Events are ordered by id, which is never reused, so the id of the
+    /// last event a client has seen can be used as a cursor.
+    #[allow(dead_code)]
+    pub async fn list_after(
+        conn: &libsql::Connection,
+        cursor: u64,
+        limit: u64,
+    ) -> Result<Vec<Self>, OrmError> {
+        let query = "select * from events
+            where id > ?1
+                and payload is not null
+            order by id asc
+            limit ?2
+        ";
+        let params = params![cursor, limit];
+
+        Orm::<Event>::query(conn, query, params).await
+    }
+
+    /// Journaled notifications in the order they were received.
+    #[allow(dead_code)]
+    pub async fn list_notifications_after(
+        conn: &libsql::Connection,
+        cursor: u64,
+        limit: u64,
+    ) -> Result<Vec<Self>, OrmError> {
+        let query = "select * from events
+            where id > ?1
+                and raw is not null
+            order by id asc
+            limit ?2
+        ";
+        let params = params![cursor, limit];
+
+        Orm::<Event>::query(conn, query, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{streams::Stream, user::User, OrmBase};
+    use chrono::Utc;
+    use libsql::{de, Builder, Connection};
+    use rand::{distributions::Alphanumeric, Rng};
+    use tracing_test::traced_test;
+
+    async fn conn() -> Connection {
+        let rand: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        let db_name = format!("test_{}.sqlite", rand.to_lowercase());
+        std::fs::copy("tests.sqlite", &db_name).unwrap();
+
+        let db = Builder::new_local(&db_name).build().await.unwrap();
+        let conn = db.connect().unwrap();
+
+        println!("Running test on {}", db_name);
+
+        conn
+    }
+
+    fn notification(message_id: &str) -> Event {
+        Event::notification(
+            message_id.to_string(),
+            "channel.follow".to_string(),
+            "2".to_string(),
+            "2025-02-10T20:00:00.123456789Z".to_string(),
+            r#"{"subscription":{},"event":{}}"#.to_string(),
+        )
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors_empty() {
+        // arrange
+        let conn = conn().await;
+        let event = Event::new();
+
+        // act
+        let res = event.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Event requires a raw notification or a payload".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors_kind() {
+        // arrange
+        let conn = conn().await;
+        let mut event = notification("abc");
+        event.kind = Some("follow".to_string());
+
+        // act
+        let res = event.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Event kind and payload must be set together".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create() {
+        // arrange
+        let conn = conn().await;
+        let event =
+            notification("abc").live("follow".to_string(), r#"{"type":"follow"}"#.to_string());
+
+        // act
+        let res = event.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 1);
+
+        let mut rows = conn
+            .query("select * from events where id = ?1 limit 1", [1])
+            .await
+            .unwrap();
+        let row = rows.next().await.unwrap().unwrap();
+        let event_st = de::from_row::<Event>(&row).unwrap();
+
+        assert_eq!(event_st.message_id, Some("abc".to_string()));
+        assert_eq!(
+            event_st.subscription_type,
+            Some("channel.follow".to_string())
+        );
+        assert_eq!(event_st.subscription_version, Some("2".to_string()));
+        assert_eq!(
+            event_st.raw,
+            Some(r#"{"subscription":{},"event":{}}"#.to_string())
+        );
+        assert_eq!(event_st.user_id, None);
+        assert_eq!(event_st.kind, Some("follow".to_string()));
+        assert_eq!(event_st.payload, Some(r#"{"type":"follow"}"#.to_string()));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_duplicate_message_id() {
+        // arrange
+        let conn = conn().await;
+        notification("abc").create(&conn).await.unwrap();
+
+        // act
+        let res = notification("abc").create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::NoChange("No event created".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn set_user_id() {
+        // arrange
+        let conn = conn().await;
+        let user_id = User::from("arinono".to_string(), 42069)
+            .create(&conn)
+            .await
+            .unwrap();
+        let id = notification("abc").create(&conn).await.unwrap();
+
+        // act
+        let res = Event::set_user_id(&conn, id, user_id).await;
+
+        // assert
+        assert!(res.is_ok());
+        let mut rows = conn
+            .query("select * from events where id = ?1 limit 1", [id])
+            .await
+            .unwrap();
+        let row = rows.next().await.unwrap().unwrap();
+        let event_st = de::from_row::<Event>(&row).unwrap();
+        assert_eq!(event_st.user_id, Some(user_id));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn set_user_id_not_found() {
+        // arrange
+        let conn = conn().await;
+
+        // act
+        let res = Event::set_user_id(&conn, 1, 1).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::NotFound("event".to_string(), Some(1))
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn set_stream_id() {
+        // arrange
+        let conn = conn().await;
+        let stream_id = Stream::from("1234".to_string(), "live".to_string(), Utc::now())
+            .create(&conn)
+            .await
+            .unwrap();
+        let id = notification("abc").create(&conn).await.unwrap();
+
+        // act
+        let res = Event::set_stream_id(&conn, id, stream_id).await;
+
+        // assert
+        assert!(res.is_ok());
+        let mut rows = conn
+            .query("select * from events where id = ?1 limit 1", [id])
+            .await
+            .unwrap();
+        let row = rows.next().await.unwrap().unwrap();
+        let event_st = de::from_row::<Event>(&row).unwrap();
+        assert_eq!(event_st.stream_id, Some(stream_id));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn list_after() {
+        // arrange
+        let conn = conn().await;
+        for (idx, kind) in ["follow", "subscribe", "cheer", "subgift"]
+            .iter()
+            .enumerate()
+        {
+            notification(&idx.to_string())
+                .live(kind.to_string(), "{}".to_string())
+                .create(&conn)
+                .await
+                .unwrap();
+        }
+        notification("unhandled").create(&conn).await.unwrap();
+
+        // act
+        let res = Event::list_after(&conn, 1, 2).await;
+        let tail = Event::list_after(&conn, 3, 10).await;
+
+        // assert
+        assert!(res.is_ok());
+        let events = res.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].id, 2);
+        assert_eq!(events[0].kind, Some("subscribe".to_string()));
+        assert_eq!(events[1].id, 3);
+        assert_eq!(events[1].kind, Some("cheer".to_string()));
+
+        let tail = tail.unwrap();
+        assert_eq!(tail.len(), 1);
+        assert_eq!(tail[0].kind, Some("subgift".to_string()));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn list_notifications_after() {
+        // arrange
+        let conn = conn().await;
+        notification("1").create(&conn).await.unwrap();
+        Event::new()
+            .live("follow".to_string(), "{}".to_string())
+            .create(&conn)
+            .await
+            .unwrap();
+        notification("3").create(&conn).await.unwrap();
+
+        // act
+        let res = Event::list_notifications_after(&conn, 0, 10).await;
+
+        // assert
+        assert!(res.is_ok());
+        let events = res.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].message_id, Some("1".to_string()));
+        assert_eq!(events[1].message_id, Some("3".to_string()));
+    }
+}
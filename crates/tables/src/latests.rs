@@ -1,24 +1,24 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use super::{Orm, OrmError};
+use super::{params, Orm, OrmError};
 
 #[allow(dead_code)]
 pub struct Latests;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[allow(dead_code)]
 pub struct LatestFollower {
     pub name: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[allow(dead_code)]
 pub struct LatestSubscriber {
     pub name: String,
     pub tier: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[allow(dead_code)]
 pub struct LatestSubgift {
     pub name: String,
@@ -26,11 +26,28 @@ pub struct LatestSubgift {
     pub number: u16,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[allow(dead_code)]
 pub struct LatestBit {
     pub name: String,
-    pub number: u16,
+    pub number: u32,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[allow(dead_code)]
+pub struct LatestRaid {
+    pub name: String,
+    pub viewers: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[allow(dead_code)]
+pub struct LatestResub {
+    pub name: String,
+    pub tier: String,
+    pub cumulative_months: u32,
+    pub streak_months: Option<u32>,
     pub message: Option<String>,
 }
 
@@ -45,7 +62,7 @@ impl Latests {
             limit 1
         ";
 
-        let rows = Orm::<LatestFollower>::query(conn, &query.to_string(), vec![]).await?;
+        let rows = Orm::<LatestFollower>::query(conn, query, params![]).await?;
 
         if rows.len() != 1 {
             return Ok(None);
@@ -64,7 +81,7 @@ impl Latests {
             limit 1
         ";
 
-        let rows = Orm::<LatestSubscriber>::query(conn, &query.to_string(), vec![]).await?;
+        let rows = Orm::<LatestSubscriber>::query(conn, query, params![]).await?;
 
         if rows.len() != 1 {
             return Ok(None);
@@ -84,7 +101,7 @@ impl Latests {
             limit 1
         ";
 
-        let rows = Orm::<LatestSubgift>::query(conn, &query.to_string(), vec![]).await?;
+        let rows = Orm::<LatestSubgift>::query(conn, query, params![]).await?;
 
         if rows.len() != 1 {
             return Ok(None);
@@ -102,7 +119,49 @@ impl Latests {
             limit 1
         ";
 
-        let rows = Orm::<LatestBit>::query(conn, &query.to_string(), vec![]).await?;
+        let rows = Orm::<LatestBit>::query(conn, query, params![]).await?;
+
+        if rows.len() != 1 {
+            return Ok(None);
+        }
+
+        Ok(Some(rows[0].clone()))
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_latest_raid(
+        conn: &libsql::Connection,
+    ) -> Result<Option<LatestRaid>, OrmError> {
+        let query = "select u.display_name name, r.viewers viewers from users u
+            inner join latests l on r.id = l.raid
+            inner join raids r on u.id = r.user_id
+            where u.deleted_at is null
+            limit 1
+        ";
+
+        let rows = Orm::<LatestRaid>::query(conn, query, params![]).await?;
+
+        if rows.len() != 1 {
+            return Ok(None);
+        }
+
+        Ok(Some(rows[0].clone()))
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_latest_resub(
+        conn: &libsql::Connection,
+    ) -> Result<Option<LatestResub>, OrmError> {
+        let query =
+            "select u.display_name name, r.tier tier, r.cumulative_months cumulative_months,
+                r.streak_months streak_months, r.message message from users u
+            inner join latests l on r.id = l.resub
+            inner join resubs r on u.id = r.user_id
+            where u.deleted_at is null
+            limit 1
+        ";
+
+        let rows = Orm::<LatestResub>::query(conn, query, params![]).await?;
 
         if rows.len() != 1 {
             return Ok(None);
@@ -115,7 +174,8 @@ impl Latests {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{bits::Bit, subgifts::Subgift, user::User, OrmBase};
+    use crate::{bits::Bit, raids::Raid, resubs::Resub, subgifts::Subgift, user::User, OrmBase};
+    use chrono::Utc;
     use libsql::{Builder, Connection};
     use rand::{distributions::Alphanumeric, Rng};
     use tracing_test::traced_test;
@@ -147,12 +207,12 @@ mod test {
     async fn latest_follower() {
         // arrange
         let conn = conn(false).await;
-        let mut time = Orm::<()>::now_utc();
+        let mut time = Utc::now();
         let user_b = User::builder("arinono".to_string(), 42069)
-            .follow(time.clone())
+            .follow(time)
             .build();
         let user2_b = User::builder("arinonono".to_string(), 42070)
-            .follow(time.clone())
+            .follow(time)
             .build();
         user_b.create(&conn).await.unwrap();
         let id = user2_b.create(&conn).await.unwrap();
@@ -167,7 +227,7 @@ mod test {
         assert_eq!(latest_follower.unwrap().name, "arinono".to_string());
 
         // arrange
-        time = Orm::<()>::now_utc();
+        time = Utc::now();
         let mut user2 = User::get(&conn, id).await.unwrap().unwrap();
         user2.follower_since = Some(time);
         user2.update(&conn).await.unwrap();
@@ -187,12 +247,12 @@ mod test {
     async fn latest_subscriber() {
         // arrange
         let conn = conn(false).await;
-        let mut time = Orm::<()>::now_utc();
+        let mut time = Utc::now();
         let user_b = User::builder("arinono".to_string(), 42069)
-            .subscribe(time.clone())
+            .subscribe(time)
             .build();
         let user2_b = User::builder("arinonono".to_string(), 42070)
-            .subscribe(time.clone())
+            .subscribe(time)
             .build();
         user_b.create(&conn).await.unwrap();
         let id = user2_b.create(&conn).await.unwrap();
@@ -212,7 +272,7 @@ mod test {
         assert_eq!(latest_subscriber.unwrap().tier, "Tier1".to_string());
 
         // arrange
-        time = Orm::<()>::now_utc();
+        time = Utc::now();
         let mut user2 = User::get(&conn, id).await.unwrap().unwrap();
         user2.subscriber_since = Some(time);
         user2.subscription_tier = Some("Prime".to_string());
@@ -316,4 +376,117 @@ mod test {
             Some("message".to_string())
         );
     }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn latest_bit_large() {
+        // arrange
+        let conn = conn(false).await;
+        let id = User::from("arinono".to_string(), 42069)
+            .create(&conn)
+            .await
+            .unwrap();
+        Bit::from(id, 100_000, None).create(&conn).await.unwrap();
+
+        // act
+        let latest_bit = Latests::get_latest_bit(&conn).await;
+
+        // assert
+        assert!(latest_bit.is_ok());
+        assert_eq!(latest_bit.unwrap().unwrap().number, 100_000);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn latest_raid() {
+        // arrange
+        let conn = conn(false).await;
+        let user_b = User::from("arinono".to_string(), 42069);
+        let user2_b = User::from("arinonono".to_string(), 42070);
+        let id = user_b.create(&conn).await.unwrap();
+        let id2 = user2_b.create(&conn).await.unwrap();
+
+        // act
+        let latest_raid = Latests::get_latest_raid(&conn).await;
+
+        // assert
+        assert!(latest_raid.is_ok());
+        assert!(latest_raid.unwrap().is_none());
+
+        // arrange
+        let raid = Raid::from(id, 12);
+        raid.create(&conn).await.unwrap();
+
+        // act
+        let latest_raid = Latests::get_latest_raid(&conn).await;
+
+        // assert
+        assert!(latest_raid.is_ok());
+        let latest_raid = latest_raid.clone().unwrap();
+        assert!(latest_raid.is_some());
+        assert_eq!(latest_raid.as_ref().unwrap().name, "arinono".to_string());
+        assert_eq!(latest_raid.as_ref().unwrap().viewers, 12);
+
+        // arrange
+        let raid = Raid::from(id2, 34);
+        raid.create(&conn).await.unwrap();
+
+        // act
+        let latest_raid = Latests::get_latest_raid(&conn).await;
+
+        // assert
+        assert!(latest_raid.is_ok());
+        let latest_raid = latest_raid.clone().unwrap();
+        assert!(latest_raid.is_some());
+        assert_eq!(latest_raid.as_ref().unwrap().name, "arinonono".to_string());
+        assert_eq!(latest_raid.as_ref().unwrap().viewers, 34);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn latest_resub() {
+        // arrange
+        let conn = conn(false).await;
+        let user_b = User::from("arinono".to_string(), 42069);
+        let user2_b = User::from("arinonono".to_string(), 42070);
+        let id = user_b.create(&conn).await.unwrap();
+        let id2 = user2_b.create(&conn).await.unwrap();
+        let resub = Resub::from(id, "Tier1".to_string(), 2, 1);
+        resub.create(&conn).await.unwrap();
+
+        // act
+        let latest_resub = Latests::get_latest_resub(&conn).await;
+
+        // assert
+        assert!(latest_resub.is_ok());
+        let latest_resub = latest_resub.clone().unwrap();
+        assert!(latest_resub.is_some());
+        assert_eq!(latest_resub.as_ref().unwrap().name, "arinono".to_string());
+        assert_eq!(latest_resub.as_ref().unwrap().tier, "Tier1".to_string());
+        assert_eq!(latest_resub.as_ref().unwrap().cumulative_months, 2);
+        assert!(latest_resub.as_ref().unwrap().streak_months.is_none());
+        assert!(latest_resub.as_ref().unwrap().message.is_none());
+
+        // arrange
+        let mut resub = Resub::from(id2, "Tier3".to_string(), 24, 1);
+        resub.streak_months = Some(12);
+        resub.message = Some("message".to_string());
+        resub.create(&conn).await.unwrap();
+
+        // act
+        let latest_resub = Latests::get_latest_resub(&conn).await;
+
+        // assert
+        assert!(latest_resub.is_ok());
+        let latest_resub = latest_resub.clone().unwrap();
+        assert!(latest_resub.is_some());
+        assert_eq!(latest_resub.as_ref().unwrap().name, "arinonono".to_string());
+        assert_eq!(latest_resub.as_ref().unwrap().tier, "Tier3".to_string());
+        assert_eq!(latest_resub.as_ref().unwrap().cumulative_months, 24);
+        assert_eq!(latest_resub.as_ref().unwrap().streak_months, Some(12));
+        assert_eq!(
+            latest_resub.as_ref().unwrap().message,
+            Some("message".to_string())
+        );
+    }
 }
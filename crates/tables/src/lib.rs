@@ -4,14 +4,29 @@ use std::{
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use chrono::{DateTime, Utc};
-use libsql::{de, Connection};
+use libsql::{de, Connection, Value};
 use serde::Deserialize;
 use tracing::{error, info};
 
+// lets the derives refer to `::tables` from inside this crate too
+extern crate self as tables;
+
+pub use tables_derive::OrmBase;
+
 pub mod bits;
+pub mod dead_letters;
+pub mod events;
 pub mod latests;
+pub mod outbox;
+pub mod query;
+pub mod raids;
+pub mod recaps;
+pub mod redemptions;
+pub mod resubs;
+pub mod streams;
 pub mod subgifts;
+pub mod timestamp;
+pub mod transaction;
 pub mod user;
 
 #[derive(Debug, Deserialize)]
@@ -36,8 +51,6 @@ impl From<twitch_types::UserId> for TwitchId {
 }
 
 const SQL_NOW_UTC_ISO: &str = "strftime('%Y-%m-%dT%H:%M:%fZ', 'now')";
-#[allow(dead_code)]
-const CHRONO_UTC_ISO_FMT: &str = "%Y-%m-%dT%H:%M:%S.%3fZ";
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum OrmError {
@@ -49,6 +62,22 @@ pub enum OrmError {
     Unknown,
 }
 
+impl std::fmt::Display for OrmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrmError::NotFound(what, Some(id)) => write!(f, "Not found: {} ({})", what, id),
+            OrmError::NotFound(what, None) => write!(f, "Not found: {}", what),
+            OrmError::BadInput(e) => write!(f, "Bad input: {}", e),
+            OrmError::QueryError(e) => write!(f, "Query error: {}", e),
+            OrmError::Deserialisation(e) => write!(f, "Deserialisation error: {}", e),
+            OrmError::NoChange(e) => write!(f, "No change: {}", e),
+            OrmError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+impl std::error::Error for OrmError {}
+
 impl From<serde::de::value::Error> for OrmError {
     fn from(value: serde::de::value::Error) -> Self {
         tracing::error!(kind = "process_error", error = value.to_string());
@@ -98,40 +127,18 @@ impl<T> Orm<T>
 where
     T: for<'de> Deserialize<'de> + Debug,
 {
-    pub fn placeholders(columns: usize) -> String {
-        let indices: Vec<usize> = (1..=columns).collect();
-        let placeholders: Vec<String> = indices.iter().map(|i| format!("?{}", i)).collect();
-        placeholders.join(", ")
-    }
-
-    pub fn update_placeholders(columns: &Vec<&str>) -> String {
-        let updates = columns
-            .iter()
-            .enumerate()
-            .map(|(idx, col)| format!("{} = ?{}", col, idx + 1))
-            .collect::<Vec<String>>();
-        updates.join(", ")
-    }
-
     fn now_ts() -> Duration {
         SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
     }
 
-    #[allow(dead_code)]
-    pub fn now_utc() -> String {
-        let now = SystemTime::now();
-        let dt: DateTime<Utc> = now.into();
-        format!("{}", dt.format("%+")).to_string()
-    }
-
     pub async fn query(
         conn: &libsql::Connection,
-        query: &String,
-        replacements: Vec<String>,
+        query: &str,
+        params: Vec<Value>,
     ) -> Result<Vec<T>, OrmError> {
         let qs = Orm::<T>::now_ts();
 
-        let mut rows = match conn.query(query, replacements).await {
+        let mut rows = match conn.query(query, params).await {
             Ok(r) => r,
             Err(e) => {
                 let qe = Orm::<T>::now_ts();
@@ -185,12 +192,12 @@ where
 
     pub async fn execute(
         conn: &libsql::Connection,
-        query: &String,
-        replacements: Vec<String>,
+        query: &str,
+        params: Vec<Value>,
     ) -> Result<u64, OrmError> {
         let qs = Orm::<T>::now_ts();
 
-        let affected_rows = match conn.execute(query, replacements).await {
+        let affected_rows = match conn.execute(query, params).await {
             Ok(r) => r,
             Err(e) => {
                 let qe = Orm::<T>::now_ts();
@@ -219,13 +226,3 @@ where
         Ok(affected_rows)
     }
 }
-
-#[macro_export]
-macro_rules! add_if_present {
-    ($columns:expr, $replacements:expr, $instance:expr, $field:ident) => {
-        if let Some(value) = &$instance.$field {
-            $columns.push(stringify!($field));
-            $replacements.push(value.to_string());
-        }
-    };
-}
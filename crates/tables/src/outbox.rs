@@ -0,0 +1,293 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    params,
+    query::{Insert, Model},
+    Orm, OrmError, RowId, SQL_NOW_UTC_ISO,
+};
+
+/// A notification waiting to be delivered to one sink target. Rows are only
+/// deleted once delivered, which gives at-least-once delivery across restarts.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Model)]
+#[orm(table = "outbox")]
+pub struct OutboxMessage {
+    pub id: u64,
+    pub sink: String,
+    pub target: String,
+    pub payload: String,
+    #[orm(skip)]
+    pub attempts: u32,
+    #[orm(skip)]
+    pub error: Option<String>,
+    #[orm(skip)]
+    #[serde(with = "crate::timestamp")]
+    pub available_at: DateTime<Utc>,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Default for OutboxMessage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `available_at` in `delay` seconds, formatted like `SQL_NOW_UTC_ISO`.
+fn sql_in(delay: &str) -> String {
+    format!(
+        "strftime('%Y-%m-%dT%H:%M:%fZ', 'now', '+' || {} || ' seconds')",
+        delay
+    )
+}
+
+impl OutboxMessage {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self {
+            id: 0,
+            sink: String::new(),
+            target: String::new(),
+            payload: String::new(),
+            attempts: 0,
+            error: None,
+            available_at: Utc::now(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from(sink: String, target: String, payload: String) -> Self {
+        Self {
+            sink,
+            target,
+            payload,
+            ..Self::new()
+        }
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self) -> Result<(), OrmError> {
+        if self.sink.is_empty() || self.target.is_empty() {
+            return Err(OrmError::BadInput(
+                "Outbox message requires a sink and a target".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Available right away.
+    #[allow(dead_code)]
+    pub async fn create(&self, conn: &libsql::Connection) -> Result<u64, OrmError> {
+        self.validate()?;
+
+        let (query, params) = Insert::model(self)
+            .now("available_at")
+            .now("created_at")
+            .build();
+
+        let rows = Orm::<RowId>::query(conn, &query, params).await?;
+
+        match rows.first() {
+            None => Err(OrmError::NoChange("No outbox message created".to_string())),
+            Some(row) => Ok(row.id),
+        }
+    }
+
+    /// Messages whose `available_at` has passed, oldest first.
+    #[allow(dead_code)]
+    pub async fn due(conn: &libsql::Connection, limit: u64) -> Result<Vec<Self>, OrmError> {
+        let query = format!(
+            "select * from outbox
+                where available_at <= {}
+                order by available_at asc, id asc
+                limit ?1
+            ",
+            SQL_NOW_UTC_ISO,
+        );
+        let params = params![limit];
+
+        Orm::<OutboxMessage>::query(conn, &query, params).await
+    }
+
+    /// Puts the message back for another try in `delay` seconds.
+    #[allow(dead_code)]
+    pub async fn reschedule(
+        conn: &libsql::Connection,
+        id: u64,
+        attempts: u32,
+        delay: f64,
+        error: &str,
+    ) -> Result<(), OrmError> {
+        let query = format!(
+            "update outbox set attempts = ?2, error = ?3, available_at = {}
+                where id = ?1
+            ",
+            sql_in("?4"),
+        );
+        let params = params![id, attempts, error, delay,];
+
+        let affected = Orm::<OutboxMessage>::execute(conn, &query, params).await?;
+
+        if affected == 0 {
+            return Err(OrmError::NotFound("outbox message".to_string(), Some(id)));
+        }
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn delete(conn: &libsql::Connection, id: u64) -> Result<(), OrmError> {
+        let query = "delete from outbox where id = ?1";
+
+        Orm::<OutboxMessage>::execute(conn, query, params![id]).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libsql::{Builder, Connection};
+    use rand::{distributions::Alphanumeric, Rng};
+    use tracing_test::traced_test;
+
+    async fn conn() -> Connection {
+        let rand: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        let db_name = format!("test_{}.sqlite", rand.to_lowercase());
+        std::fs::copy("tests.sqlite", &db_name).unwrap();
+
+        let db = Builder::new_local(&db_name).build().await.unwrap();
+        let conn = db.connect().unwrap();
+
+        println!("Running test on {}", db_name);
+
+        conn
+    }
+
+    fn message(payload: &str) -> OutboxMessage {
+        OutboxMessage::from(
+            "discord".to_string(),
+            "default".to_string(),
+            payload.to_string(),
+        )
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors() {
+        // arrange
+        let conn = conn().await;
+        let message = OutboxMessage::new();
+
+        // act
+        let res = message.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Outbox message requires a sink and a target".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create() {
+        // arrange
+        let conn = conn().await;
+
+        // act
+        let res = message("{}").create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 1);
+
+        let due = OutboxMessage::due(&conn, 10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].sink, "discord".to_string());
+        assert_eq!(due[0].target, "default".to_string());
+        assert_eq!(due[0].payload, "{}".to_string());
+        assert_eq!(due[0].attempts, 0);
+        assert_eq!(due[0].error, None);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn due() {
+        // arrange
+        let conn = conn().await;
+        let first = message("1").create(&conn).await.unwrap();
+        let second = message("2").create(&conn).await.unwrap();
+        message("3").create(&conn).await.unwrap();
+        OutboxMessage::reschedule(&conn, first, 1, 3600.0, "502 Bad Gateway")
+            .await
+            .unwrap();
+
+        // act
+        let res = OutboxMessage::due(&conn, 1).await;
+
+        // assert
+        assert!(res.is_ok());
+        let due = res.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, second);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn reschedule() {
+        // arrange
+        let conn = conn().await;
+        let id = message("{}").create(&conn).await.unwrap();
+
+        // act
+        let res = OutboxMessage::reschedule(&conn, id, 2, 0.0, "429 Too Many Requests").await;
+
+        // assert
+        assert!(res.is_ok());
+        let due = OutboxMessage::due(&conn, 10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].attempts, 2);
+        assert_eq!(due[0].error, Some("429 Too Many Requests".to_string()));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn reschedule_not_found() {
+        // arrange
+        let conn = conn().await;
+
+        // act
+        let res = OutboxMessage::reschedule(&conn, 42, 1, 1.0, "boom").await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::NotFound("outbox message".to_string(), Some(42))
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn delete() {
+        // arrange
+        let conn = conn().await;
+        let id = message("{}").create(&conn).await.unwrap();
+
+        // act
+        let res = OutboxMessage::delete(&conn, id).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert!(OutboxMessage::due(&conn, 10).await.unwrap().is_empty());
+    }
+}
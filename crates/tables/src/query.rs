@@ -0,0 +1,507 @@
+use chrono::{DateTime, Utc};
+use libsql::Value;
+
+pub use tables_derive::Model;
+
+use super::SQL_NOW_UTC_ISO;
+
+/// Converts a field into the `libsql::Value` it is bound as, so integers are
+/// compared as integers and `None` is stored as `null`.
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
+
+macro_rules! integer_value {
+    ($($ty:ty),*) => {
+        $(impl ToValue for $ty {
+            fn to_value(&self) -> Value {
+                Value::Integer(*self as i64)
+            }
+        })*
+    };
+}
+
+integer_value!(i64, i32, u32, u16, u8);
+
+/// SQLite integers are signed, values above `i64::MAX` are kept as text
+/// rather than wrapped around.
+impl ToValue for u64 {
+    fn to_value(&self) -> Value {
+        match i64::try_from(*self) {
+            Ok(value) => Value::Integer(value),
+            Err(_) => Value::Text(self.to_string()),
+        }
+    }
+}
+
+impl ToValue for usize {
+    fn to_value(&self) -> Value {
+        (*self as u64).to_value()
+    }
+}
+
+impl ToValue for bool {
+    fn to_value(&self) -> Value {
+        Value::Integer(*self as i64)
+    }
+}
+
+impl ToValue for f64 {
+    fn to_value(&self) -> Value {
+        Value::Real(*self)
+    }
+}
+
+impl ToValue for str {
+    fn to_value(&self) -> Value {
+        Value::Text(self.to_string())
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> Value {
+        Value::Text(self.clone())
+    }
+}
+
+impl ToValue for DateTime<Utc> {
+    fn to_value(&self) -> Value {
+        Value::Text(super::timestamp::format(self))
+    }
+}
+
+impl<T: ToValue + ?Sized> ToValue for &T {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> Value {
+        match self {
+            Some(value) => value.to_value(),
+            None => Value::Null,
+        }
+    }
+}
+
+/// Builds the parameters of a query, each bound with its own type.
+///
+/// ```ignore
+/// Orm::<User>::query(conn, "select * from users where id = ?1", params![id]).await
+/// ```
+#[macro_export]
+macro_rules! params {
+    () => {
+        Vec::<libsql::Value>::new()
+    };
+    ($($value:expr),+ $(,)?) => {
+        vec![$($crate::query::ToValue::to_value(&$value)),+]
+    };
+}
+
+/// A column a model writes, see `Model::columns`.
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: &'static str,
+    /// `None` leaves the column out of the statement, so it keeps its
+    /// default on insert and its current value on update.
+    pub value: Option<Value>,
+}
+
+impl Column {
+    pub fn new(name: &'static str, value: impl ToValue) -> Self {
+        Self {
+            name,
+            value: Some(value.to_value()),
+        }
+    }
+
+    /// Left out when `value` is `None`.
+    pub fn optional<T: ToValue>(name: &'static str, value: &Option<T>) -> Self {
+        Self {
+            name,
+            value: value.as_ref().map(ToValue::to_value),
+        }
+    }
+}
+
+/// Describes how a model maps onto its table.
+pub trait Model {
+    const TABLE: &'static str;
+
+    /// The columns `Insert::model` and `Update::model` write. Ids and
+    /// timestamps managed by the database are not part of it.
+    fn columns(&self) -> Vec<Column>;
+}
+
+/// Numbers placeholders as values get bound, so a statement never refers to
+/// the wrong parameter.
+#[derive(Debug, Default)]
+struct Params(Vec<Value>);
+
+impl Params {
+    fn bind(&mut self, value: Value) -> String {
+        self.0.push(value);
+        format!("?{}", self.0.len())
+    }
+}
+
+/// An `insert` statement, e.g.
+///
+/// ```ignore
+/// let (query, params) = Insert::model(&bit).timestamp("created_at", &bit.created_at).build();
+/// ```
+#[derive(Debug)]
+pub struct Insert {
+    table: &'static str,
+    columns: Vec<&'static str>,
+    values: Vec<String>,
+    params: Params,
+    on_conflict: Option<String>,
+    returning: Option<&'static str>,
+}
+
+impl Insert {
+    pub fn into(table: &'static str) -> Self {
+        Self {
+            table,
+            columns: vec![],
+            values: vec![],
+            params: Params::default(),
+            on_conflict: None,
+            returning: Some("id"),
+        }
+    }
+
+    pub fn model<M: Model>(model: &M) -> Self {
+        Self::into(M::TABLE).columns(model.columns())
+    }
+
+    pub fn columns(self, columns: Vec<Column>) -> Self {
+        columns
+            .into_iter()
+            .fold(self, |insert, column| match column.value {
+                Some(value) => insert.bind(column.name, value),
+                None => insert,
+            })
+    }
+
+    pub fn value(self, column: &'static str, value: impl ToValue) -> Self {
+        self.bind(column, value.to_value())
+    }
+
+    fn bind(mut self, column: &'static str, value: Value) -> Self {
+        let placeholder = self.params.bind(value);
+        self.columns.push(column);
+        self.values.push(placeholder);
+        self
+    }
+
+    /// Sets the column to a SQL expression, e.g. `SQL_NOW_UTC_ISO`.
+    pub fn expr(mut self, column: &'static str, sql: &str) -> Self {
+        self.columns.push(column);
+        self.values.push(sql.to_string());
+        self
+    }
+
+    pub fn now(self, column: &'static str) -> Self {
+        self.expr(column, SQL_NOW_UTC_ISO)
+    }
+
+    /// An empty `value` falls back to now.
+    pub fn timestamp(mut self, column: &'static str, value: impl ToValue) -> Self {
+        let placeholder = self.params.bind(value.to_value());
+        self.columns.push(column);
+        self.values.push(format!(
+            "coalesce(nullif({}, ''), {})",
+            placeholder, SQL_NOW_UTC_ISO
+        ));
+        self
+    }
+
+    /// e.g. `on conflict (message_id) do nothing`, without the leading
+    /// `on conflict`.
+    pub fn on_conflict(mut self, clause: &str) -> Self {
+        self.on_conflict = Some(clause.to_string());
+        self
+    }
+
+    /// Statements return the new `id` by default.
+    pub fn returning(mut self, column: Option<&'static str>) -> Self {
+        self.returning = column;
+        self
+    }
+
+    pub fn build(self) -> (String, Vec<Value>) {
+        let mut query = format!(
+            "insert into {} ({}) values ({})",
+            self.table,
+            self.columns.join(", "),
+            self.values.join(", "),
+        );
+        if let Some(clause) = self.on_conflict {
+            query.push_str(&format!(" on conflict {}", clause));
+        }
+        if let Some(column) = self.returning {
+            query.push_str(&format!(" returning {}", column));
+        }
+
+        (query, self.params.0)
+    }
+}
+
+/// An `update` statement, e.g.
+///
+/// ```ignore
+/// let (query, params) = Update::model(&user).filter("id", user.id).build();
+/// ```
+#[derive(Debug)]
+pub struct Update {
+    table: &'static str,
+    sets: Vec<String>,
+    filters: Vec<String>,
+    params: Params,
+}
+
+impl Update {
+    pub fn table(table: &'static str) -> Self {
+        Self {
+            table,
+            sets: vec![],
+            filters: vec![],
+            params: Params::default(),
+        }
+    }
+
+    pub fn model<M: Model>(model: &M) -> Self {
+        Self::table(M::TABLE).columns(model.columns())
+    }
+
+    pub fn columns(self, columns: Vec<Column>) -> Self {
+        columns
+            .into_iter()
+            .fold(self, |update, column| match column.value {
+                Some(value) => update.bind(column.name, value),
+                None => update,
+            })
+    }
+
+    pub fn set(self, column: &'static str, value: impl ToValue) -> Self {
+        self.bind(column, value.to_value())
+    }
+
+    fn bind(mut self, column: &'static str, value: Value) -> Self {
+        let placeholder = self.params.bind(value);
+        self.sets.push(format!("{} = {}", column, placeholder));
+        self
+    }
+
+    /// Sets the column to a SQL expression, e.g. `SQL_NOW_UTC_ISO`.
+    pub fn expr(mut self, column: &'static str, sql: &str) -> Self {
+        self.sets.push(format!("{} = {}", column, sql));
+        self
+    }
+
+    pub fn now(self, column: &'static str) -> Self {
+        self.expr(column, SQL_NOW_UTC_ISO)
+    }
+
+    /// An empty `value` falls back to now.
+    pub fn timestamp(mut self, column: &'static str, value: impl ToValue) -> Self {
+        let placeholder = self.params.bind(value.to_value());
+        self.sets.push(format!(
+            "{} = coalesce(nullif({}, ''), {})",
+            column, placeholder, SQL_NOW_UTC_ISO
+        ));
+        self
+    }
+
+    /// Adds a `column = value` condition, conditions are and-ed together.
+    pub fn filter(mut self, column: &'static str, value: impl ToValue) -> Self {
+        let placeholder = self.params.bind(value.to_value());
+        self.filters.push(format!("{} = {}", column, placeholder));
+        self
+    }
+
+    /// Adds a `column in (values)` condition, which no row matches when
+    /// `values` is empty.
+    pub fn filter_in<T: ToValue>(mut self, column: &'static str, values: &[T]) -> Self {
+        let placeholders: Vec<String> = values
+            .iter()
+            .map(|value| self.params.bind(value.to_value()))
+            .collect();
+        self.filters
+            .push(format!("{} in ({})", column, placeholders.join(", ")));
+        self
+    }
+
+    /// Adds a condition without parameters, e.g. `deleted_at is null`.
+    pub fn filter_sql(mut self, condition: &str) -> Self {
+        self.filters.push(condition.to_string());
+        self
+    }
+
+    pub fn build(self) -> (String, Vec<Value>) {
+        let mut query = format!("update {} set {}", self.table, self.sets.join(", "));
+        if !self.filters.is_empty() {
+            query.push_str(&format!(" where {}", self.filters.join(" and ")));
+        }
+
+        (query, self.params.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    struct Widget {
+        name: String,
+        size: u64,
+        label: Option<String>,
+    }
+
+    impl Model for Widget {
+        const TABLE: &'static str = "widgets";
+
+        fn columns(&self) -> Vec<Column> {
+            vec![
+                Column::new("name", &self.name),
+                Column::new("size", self.size),
+                Column::optional("label", &self.label),
+            ]
+        }
+    }
+
+    fn widget(label: Option<&str>) -> Widget {
+        Widget {
+            name: "gear".to_string(),
+            size: 42,
+            label: label.map(str::to_string),
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn params_are_typed() {
+        // act
+        let params = crate::params![42u64, "text", None::<u32>, 1.5, u64::MAX];
+
+        // assert
+        assert_eq!(
+            params,
+            vec![
+                Value::Integer(42),
+                Value::Text("text".to_string()),
+                Value::Null,
+                Value::Real(1.5),
+                Value::Text(u64::MAX.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn insert_model() {
+        // act
+        let (query, params) = Insert::model(&widget(None))
+            .timestamp("created_at", "")
+            .build();
+
+        // assert
+        assert_eq!(
+            query,
+            format!(
+                "insert into widgets (name, size, created_at) values (?1, ?2, coalesce(nullif(?3, ''), {})) returning id",
+                SQL_NOW_UTC_ISO
+            )
+        );
+        assert_eq!(
+            params,
+            vec![
+                Value::Text("gear".to_string()),
+                Value::Integer(42),
+                Value::Text(String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn insert_on_conflict() {
+        // act
+        let (query, params) = Insert::into("widgets")
+            .value("name", "gear")
+            .expr("created_at", SQL_NOW_UTC_ISO)
+            .on_conflict("(name) do nothing")
+            .returning(None)
+            .build();
+
+        // assert
+        assert_eq!(
+            query,
+            format!(
+                "insert into widgets (name, created_at) values (?1, {}) on conflict (name) do nothing",
+                SQL_NOW_UTC_ISO
+            )
+        );
+        assert_eq!(params, vec![Value::Text("gear".to_string())]);
+    }
+
+    #[test]
+    #[traced_test]
+    fn update_model() {
+        // act
+        let (query, params) = Update::model(&widget(Some("big")))
+            .expr("updated_at", SQL_NOW_UTC_ISO)
+            .filter("id", 7u64)
+            .filter_sql("deleted_at is null")
+            .build();
+
+        // assert
+        assert_eq!(
+            query,
+            format!(
+                "update widgets set name = ?1, size = ?2, label = ?3, updated_at = {} where id = ?4 and deleted_at is null",
+                SQL_NOW_UTC_ISO
+            )
+        );
+        assert_eq!(
+            params,
+            vec![
+                Value::Text("gear".to_string()),
+                Value::Integer(42),
+                Value::Text("big".to_string()),
+                Value::Integer(7),
+            ]
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn update_filter_in() {
+        // act
+        let (query, params) = Update::table("widgets")
+            .set("size", 0u64)
+            .filter_in("id", &[1u64, 2, 3])
+            .build();
+
+        // assert
+        assert_eq!(
+            query,
+            "update widgets set size = ?1 where id in (?2, ?3, ?4)".to_string()
+        );
+        assert_eq!(
+            params,
+            vec![
+                Value::Integer(0),
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(3),
+            ]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{params, query::Model, Orm, OrmBase, OrmError};
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Model, OrmBase)]
+#[orm(table = "raids", validate)]
+pub struct Raid {
+    pub id: u64,
+    pub user_id: Option<u64>,
+    pub viewers: u32,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime<Utc>,
+    pub stream_id: Option<u64>,
+}
+
+impl Default for Raid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Raid {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self {
+            id: 0,
+            user_id: None,
+            viewers: 0,
+            created_at: Utc::now(),
+            stream_id: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from(user_id: u64, viewers: u32) -> Self {
+        Self {
+            id: 0,
+            user_id: Some(user_id),
+            viewers,
+            created_at: Utc::now(),
+            stream_id: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self) -> Result<(), OrmError> {
+        if self.user_id.is_none() {
+            return Err(OrmError::BadInput("Raid requires a user".to_string()));
+        }
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn list(
+        conn: &libsql::Connection,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Self>, OrmError> {
+        let query = "select * from raids
+            order by id desc
+            limit ?1 offset ?2
+        ";
+        let params = params![limit, offset];
+
+        Orm::<Raid>::query(conn, query, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{timestamp, user::User, OrmBase};
+
+    use super::*;
+    use chrono::TimeZone;
+    use libsql::{de, Builder, Connection};
+    use rand::{distributions::Alphanumeric, Rng};
+    use tracing_test::traced_test;
+
+    async fn conn(with_user: bool) -> Connection {
+        let rand: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        let db_name = format!("test_{}.sqlite", rand.to_lowercase());
+        std::fs::copy("tests.sqlite", &db_name).unwrap();
+
+        let db = Builder::new_local(&db_name).build().await.unwrap();
+        let conn = db.connect().unwrap();
+
+        if with_user {
+            let user = User::from("arinono".to_string(), 42069);
+            user.create(&conn).await.unwrap();
+        }
+
+        println!("Running test on {}", db_name);
+
+        conn
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors() {
+        // arrange
+        let conn = conn(true).await;
+        let raid = Raid::new();
+
+        // act
+        let res = raid.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Raid requires a user".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_no_user() {
+        // arrange
+        let conn = conn(false).await;
+        let raid = Raid::from(1, 42);
+
+        // act
+        let res = raid.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::NoChange("No raid created".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create() {
+        // arrange
+        let conn = conn(true).await;
+        let raid = Raid::from(1, 42);
+
+        // act
+        let res = raid.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 1);
+
+        let mut rows = conn
+            .query("select * from raids where id = ?1 limit 1", [1])
+            .await
+            .unwrap();
+        let row = rows.next().await.unwrap().unwrap();
+        let raid_st = de::from_row::<Raid>(&row).unwrap();
+
+        assert_eq!(raid_st.id, 1);
+        assert_eq!(raid_st.user_id, Some(1));
+        assert_eq!(raid_st.viewers, 42);
+        assert!(raid_st.created_at <= Utc::now());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_created_at() {
+        // arrange
+        let conn = conn(true).await;
+        let mut raid = Raid::from(1, 42);
+        raid.created_at = Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap();
+
+        // act
+        let res = raid.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        let mut rows = conn
+            .query("select * from raids where id = ?1 limit 1", [res.unwrap()])
+            .await
+            .unwrap();
+        let row = rows.next().await.unwrap().unwrap();
+        let raid_st = de::from_row::<Raid>(&row).unwrap();
+        assert_eq!(
+            timestamp::format(&raid_st.created_at),
+            "2025-02-10T20:00:00.000Z".to_string()
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn list() {
+        // arrange
+        let conn = conn(true).await;
+        Raid::from(1, 10).create(&conn).await.unwrap();
+        Raid::from(1, 20).create(&conn).await.unwrap();
+
+        // act
+        let res = Raid::list(&conn, 10, 0).await;
+
+        // assert
+        assert!(res.is_ok());
+        let raids = res.unwrap();
+        assert_eq!(raids.len(), 2);
+        assert_eq!(raids[0].viewers, 20);
+        assert_eq!(raids[1].viewers, 10);
+    }
+}
@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+
+use super::{params, streams::Stream, Orm, OrmError, SQL_NOW_UTC_ISO};
+
+/// What happened during a stream, bounded by its `started_at` and `ended_at`
+/// (or now while it is still live). New subs are taken from the journaled
+/// `channel.subscribe` notifications, since resubs move a user's tier without
+/// telling it apart from a new subscription. Gifted ones are left to
+/// `subgifts`, which counts them already.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Recap {
+    pub new_followers: u64,
+    pub subs: Vec<RecapTier>,
+    pub subgifts: u64,
+    pub bits: u64,
+    pub top_gifter: Option<RecapContributor>,
+    pub top_cheerer: Option<RecapContributor>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RecapTier {
+    pub tier: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RecapContributor {
+    pub name: String,
+    pub total: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecapTotals {
+    new_followers: u64,
+    subgifts: u64,
+    bits: u64,
+}
+
+fn within(column: &str) -> String {
+    format!(
+        "{} between ?1 and coalesce(?2, {})",
+        column, SQL_NOW_UTC_ISO
+    )
+}
+
+impl Recap {
+    #[allow(dead_code)]
+    pub async fn for_stream(conn: &libsql::Connection, stream: &Stream) -> Result<Self, OrmError> {
+        let window = params![stream.started_at, stream.ended_at];
+
+        let query = format!(
+            "select
+                (select count(*) from users
+                    where deleted_at is null and {}) new_followers,
+                (select coalesce(sum(number), 0) from subgifts where {}) subgifts,
+                (select coalesce(sum(number), 0) from bits where {}) bits
+            ",
+            within("follower_since"),
+            within("created_at"),
+            within("created_at"),
+        );
+        let totals = Orm::<RecapTotals>::query(conn, &query, window.clone())
+            .await?
+            .pop()
+            .ok_or(OrmError::Unknown)?;
+
+        let query = format!(
+            "select json_extract(e.payload, '$.tier') tier, count(*) count from events e
+                left join users u on u.id = e.user_id
+                where e.kind = 'subscribe'
+                    and json_extract(e.payload, '$.is_gift') = 0
+                    and u.deleted_at is null
+                    and {}
+                group by tier
+                order by tier asc
+            ",
+            within("e.created_at"),
+        );
+        let subs = Orm::<RecapTier>::query(conn, &query, window.clone()).await?;
+
+        Ok(Self {
+            new_followers: totals.new_followers,
+            subs,
+            subgifts: totals.subgifts,
+            bits: totals.bits,
+            top_gifter: Recap::top(conn, "subgifts", window.clone()).await?,
+            top_cheerer: Recap::top(conn, "bits", window).await?,
+        })
+    }
+
+    async fn top(
+        conn: &libsql::Connection,
+        table: &str,
+        window: Vec<libsql::Value>,
+    ) -> Result<Option<RecapContributor>, OrmError> {
+        let query = format!(
+            "select u.display_name name, sum(t.number) total from {} t
+                inner join users u on u.id = t.user_id
+                where u.deleted_at is null and {}
+                group by u.id
+                order by total desc, u.display_name asc
+                limit 1
+            ",
+            table,
+            within("t.created_at"),
+        );
+
+        let rows = Orm::<RecapContributor>::query(conn, &query, window).await?;
+
+        Ok(rows.first().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bits::Bit, events::Event, subgifts::Subgift, user::User, OrmBase};
+
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+    use libsql::{Builder, Connection};
+    use rand::{distributions::Alphanumeric, Rng};
+    use tracing_test::traced_test;
+
+    async fn conn() -> Connection {
+        let rand: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        let db_name = format!("test_{}.sqlite", rand.to_lowercase());
+        std::fs::copy("tests.sqlite", &db_name).unwrap();
+
+        let db = Builder::new_local(&db_name).build().await.unwrap();
+        let conn = db.connect().unwrap();
+
+        println!("Running test on {}", db_name);
+
+        conn
+    }
+
+    fn stream() -> Stream {
+        let mut stream = Stream::from(
+            "1234".to_string(),
+            "live".to_string(),
+            Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap(),
+        );
+        stream.ended_at = Some(Utc.with_ymd_and_hms(2025, 2, 10, 23, 0, 0).unwrap());
+        stream
+    }
+
+    async fn user(
+        conn: &Connection,
+        name: &str,
+        twitch_id: u64,
+        at: DateTime<Utc>,
+        tier: &str,
+    ) -> u64 {
+        let mut user = User::from(name.to_string(), twitch_id);
+        user.follower_since = Some(at);
+        if !tier.is_empty() {
+            user.subscriber_since = Some(at);
+            user.subscription_tier = Some(tier.to_string());
+        }
+        user.create(conn).await.unwrap()
+    }
+
+    async fn event(
+        conn: &Connection,
+        kind: &str,
+        user_id: u64,
+        at: DateTime<Utc>,
+        tier: &str,
+        is_gift: bool,
+    ) {
+        let payload = format!(
+            r#"{{"type":"{}","user_id":"{}","tier":"{}","is_gift":{}}}"#,
+            kind, user_id, tier, is_gift
+        );
+        let mut event = Event::new().live(kind.to_string(), payload);
+        event.user_id = Some(user_id);
+        event.created_at = at;
+        event.create(conn).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn for_stream_empty() {
+        // arrange
+        let conn = conn().await;
+
+        // act
+        let res = Recap::for_stream(&conn, &stream()).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap(),
+            Recap {
+                new_followers: 0,
+                subs: vec![],
+                subgifts: 0,
+                bits: 0,
+                top_gifter: None,
+                top_cheerer: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn for_stream() {
+        // arrange
+        let conn = conn().await;
+        let before = Utc.with_ymd_and_hms(2025, 2, 10, 19, 0, 0).unwrap();
+        let during = Utc.with_ymd_and_hms(2025, 2, 10, 21, 0, 0).unwrap();
+        let after = Utc.with_ymd_and_hms(2025, 2, 11, 0, 0, 0).unwrap();
+
+        let arinono = user(&conn, "arinono", 1, during, "Tier1").await;
+        let jdoe = user(&conn, "jdoe", 2, during, "Tier1").await;
+        user(&conn, "oldie", 3, before, "Tier3").await;
+        user(&conn, "late", 4, after, "Tier2").await;
+        let lurker = user(&conn, "lurker", 5, during, "").await;
+
+        let stream = stream();
+        event(&conn, "subscribe", arinono, during, "Tier1", false).await;
+        event(&conn, "subscribe", jdoe, during, "Tier1", false).await;
+        event(&conn, "resubscribe", jdoe, during, "Tier1", false).await;
+        event(&conn, "subscribe", lurker, during, "Tier1", true).await;
+        event(&conn, "subscribe", jdoe, before, "Tier3", false).await;
+        event(&conn, "subscribe", jdoe, after, "Tier2", false).await;
+
+        for (user_id, number, at) in [(arinono, 5, during), (jdoe, 2, during), (jdoe, 9, after)] {
+            let mut subgift = Subgift::from(user_id, number, "Tier1".to_string());
+            subgift.created_at = at;
+            subgift.create(&conn).await.unwrap();
+        }
+        let mut subgift = Subgift::from_anonymous(1, "Tier1".to_string());
+        subgift.created_at = during;
+        subgift.create(&conn).await.unwrap();
+
+        for (user_id, number, at) in [
+            (arinono, 100, during),
+            (jdoe, 500, during),
+            (jdoe, 1, before),
+        ] {
+            let mut bit = Bit::from(user_id, number, None);
+            bit.created_at = at;
+            bit.create(&conn).await.unwrap();
+        }
+
+        // act
+        let res = Recap::for_stream(&conn, &stream).await;
+
+        // assert
+        assert!(res.is_ok());
+        let recap = res.unwrap();
+        assert_eq!(recap.new_followers, 3);
+        assert_eq!(
+            recap.subs,
+            vec![RecapTier {
+                tier: "Tier1".to_string(),
+                count: 2,
+            }]
+        );
+        assert_eq!(recap.subgifts, 8);
+        assert_eq!(recap.bits, 600);
+        assert_eq!(
+            recap.top_gifter,
+            Some(RecapContributor {
+                name: "arinono".to_string(),
+                total: 5,
+            })
+        );
+        assert_eq!(
+            recap.top_cheerer,
+            Some(RecapContributor {
+                name: "jdoe".to_string(),
+                total: 500,
+            })
+        );
+    }
+}
@@ -0,0 +1,421 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    params,
+    query::{Model, Update},
+    Orm, OrmBase, OrmError,
+};
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Model, OrmBase)]
+#[orm(table = "redemptions", validate)]
+pub struct Redemption {
+    pub id: u64,
+    pub redemption_id: String,
+    pub user_id: Option<u64>,
+    pub reward_id: String,
+    pub title: String,
+    pub cost: u64,
+    pub user_input: Option<String>,
+    pub status: String,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime<Utc>,
+    #[orm(from_created_at)]
+    #[serde(with = "crate::timestamp")]
+    pub updated_at: DateTime<Utc>,
+    pub stream_id: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct RewardLeaderboardEntry {
+    pub name: String,
+    pub redemptions: u64,
+    pub points: u64,
+}
+
+impl Default for Redemption {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Redemption {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self {
+            id: 0,
+            redemption_id: String::new(),
+            user_id: None,
+            reward_id: String::new(),
+            title: String::new(),
+            cost: 0,
+            user_input: None,
+            status: "unfulfilled".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            stream_id: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from(
+        user_id: u64,
+        redemption_id: String,
+        reward_id: String,
+        title: String,
+        cost: u64,
+    ) -> Self {
+        Self {
+            user_id: Some(user_id),
+            redemption_id,
+            reward_id,
+            title,
+            cost,
+            ..Self::new()
+        }
+    }
+
+    fn validate_status(status: &str) -> Result<(), OrmError> {
+        match status {
+            "unfulfilled" | "fulfilled" | "canceled" | "unknown" => Ok(()),
+            _ => Err(OrmError::BadInput("Invalid redemption status".to_string())),
+        }
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self) -> Result<(), OrmError> {
+        if self.user_id.is_none() {
+            return Err(OrmError::BadInput("Redemption requires a user".to_string()));
+        }
+
+        if self.redemption_id.is_empty() || self.reward_id.is_empty() {
+            return Err(OrmError::BadInput(
+                "Redemption requires a redemption id and a reward id".to_string(),
+            ));
+        }
+
+        Redemption::validate_status(&self.status)
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_by_redemption_id(
+        conn: &libsql::Connection,
+        redemption_id: &str,
+    ) -> Result<Option<Self>, OrmError> {
+        let query = "select * from redemptions
+            where redemption_id = ?1
+            limit 1
+        ";
+        let params = params![redemption_id];
+
+        let rows = Orm::<Redemption>::query(conn, query, params).await?;
+
+        Ok(rows.first().cloned())
+    }
+
+    #[allow(dead_code)]
+    pub async fn update_status(
+        conn: &libsql::Connection,
+        redemption_id: &str,
+        status: &str,
+        at: DateTime<Utc>,
+    ) -> Result<(), OrmError> {
+        Redemption::validate_status(status)?;
+
+        let (query, params) = Update::table(Redemption::TABLE)
+            .set("status", status)
+            .timestamp("updated_at", at)
+            .filter("redemption_id", redemption_id)
+            .build();
+
+        let affected = Orm::<Redemption>::execute(conn, &query, params).await?;
+
+        if affected == 0 {
+            return Err(OrmError::NotFound(
+                format!("redemption {}", redemption_id),
+                None,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Users who redeemed a reward the most, canceled redemptions excluded.
+    #[allow(dead_code)]
+    pub async fn leaderboard(
+        conn: &libsql::Connection,
+        reward_id: &str,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<RewardLeaderboardEntry>, OrmError> {
+        let query = "select u.display_name name, count(r.id) redemptions, sum(r.cost) points
+            from redemptions r
+            inner join users u on u.id = r.user_id
+            where r.reward_id = ?1
+                and r.status != 'canceled'
+                and u.deleted_at is null
+            group by u.id
+            order by redemptions desc, points desc, u.display_name asc
+            limit ?2 offset ?3
+        ";
+        let params = params![reward_id, limit, offset];
+
+        Orm::<RewardLeaderboardEntry>::query(conn, query, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{timestamp, user::User, OrmBase};
+
+    use super::*;
+    use chrono::TimeZone;
+    use libsql::{de, Builder, Connection};
+    use rand::{distributions::Alphanumeric, Rng};
+    use tracing_test::traced_test;
+
+    async fn conn(with_user: bool) -> Connection {
+        let rand: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        let db_name = format!("test_{}.sqlite", rand.to_lowercase());
+        std::fs::copy("tests.sqlite", &db_name).unwrap();
+
+        let db = Builder::new_local(&db_name).build().await.unwrap();
+        let conn = db.connect().unwrap();
+
+        if with_user {
+            let user = User::from("arinono".to_string(), 42069);
+            user.create(&conn).await.unwrap();
+        }
+
+        println!("Running test on {}", db_name);
+
+        conn
+    }
+
+    fn redemption(user_id: u64, redemption_id: &str, reward_id: &str) -> Redemption {
+        Redemption::from(
+            user_id,
+            redemption_id.to_string(),
+            reward_id.to_string(),
+            "Hydrate".to_string(),
+            100,
+        )
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors_user() {
+        // arrange
+        let conn = conn(true).await;
+        let mut redemption = redemption(1, "abc", "reward");
+        redemption.user_id = None;
+
+        // act
+        let res = redemption.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Redemption requires a user".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors_ids() {
+        // arrange
+        let conn = conn(true).await;
+        let redemption = redemption(1, "", "reward");
+
+        // act
+        let res = redemption.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Redemption requires a redemption id and a reward id".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors_status() {
+        // arrange
+        let conn = conn(true).await;
+        let mut redemption = redemption(1, "abc", "reward");
+        redemption.status = "pending".to_string();
+
+        // act
+        let res = redemption.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Invalid redemption status".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_no_user() {
+        // arrange
+        let conn = conn(false).await;
+        let redemption = redemption(1, "abc", "reward");
+
+        // act
+        let res = redemption.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::NoChange("No redemption created".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create() {
+        // arrange
+        let conn = conn(true).await;
+        let mut redemption = redemption(1, "abc", "reward");
+        redemption.user_input = Some("drink water".to_string());
+
+        // act
+        let res = redemption.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 1);
+
+        let mut rows = conn
+            .query("select * from redemptions where id = ?1 limit 1", [1])
+            .await
+            .unwrap();
+        let row = rows.next().await.unwrap().unwrap();
+        let redemption_st = de::from_row::<Redemption>(&row).unwrap();
+
+        assert_eq!(redemption_st.id, 1);
+        assert_eq!(redemption_st.redemption_id, "abc".to_string());
+        assert_eq!(redemption_st.user_id, Some(1));
+        assert_eq!(redemption_st.reward_id, "reward".to_string());
+        assert_eq!(redemption_st.title, "Hydrate".to_string());
+        assert_eq!(redemption_st.cost, 100);
+        assert_eq!(redemption_st.user_input, Some("drink water".to_string()));
+        assert_eq!(redemption_st.status, "unfulfilled".to_string());
+        assert!(redemption_st.created_at <= Utc::now());
+        assert_eq!(redemption_st.created_at, redemption_st.updated_at);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn get_by_redemption_id() {
+        // arrange
+        let conn = conn(true).await;
+        redemption(1, "abc", "reward").create(&conn).await.unwrap();
+
+        // act
+        let found = Redemption::get_by_redemption_id(&conn, "abc").await;
+        let missing = Redemption::get_by_redemption_id(&conn, "def").await;
+
+        // assert
+        assert!(found.is_ok());
+        assert_eq!(found.unwrap().unwrap().redemption_id, "abc".to_string());
+        assert!(missing.is_ok());
+        assert!(missing.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn update_status() {
+        // arrange
+        let conn = conn(true).await;
+        redemption(1, "abc", "reward").create(&conn).await.unwrap();
+
+        // act
+        let res = Redemption::update_status(
+            &conn,
+            "abc",
+            "fulfilled",
+            Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap(),
+        )
+        .await;
+
+        // assert
+        assert!(res.is_ok());
+        let redemption_st = Redemption::get_by_redemption_id(&conn, "abc")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(redemption_st.status, "fulfilled".to_string());
+        assert_eq!(
+            timestamp::format(&redemption_st.updated_at),
+            "2025-02-10T20:00:00.000Z".to_string()
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn update_status_not_found() {
+        // arrange
+        let conn = conn(true).await;
+
+        // act
+        let res = Redemption::update_status(&conn, "abc", "fulfilled", Utc::now()).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::NotFound("redemption abc".to_string(), None)
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn leaderboard() {
+        // arrange
+        let conn = conn(true).await;
+        let id2 = User::from("arinonono".to_string(), 42070)
+            .create(&conn)
+            .await
+            .unwrap();
+        redemption(1, "1", "reward").create(&conn).await.unwrap();
+        redemption(id2, "2", "reward").create(&conn).await.unwrap();
+        redemption(id2, "3", "reward").create(&conn).await.unwrap();
+        redemption(1, "4", "other").create(&conn).await.unwrap();
+        redemption(1, "5", "other").create(&conn).await.unwrap();
+        let mut canceled = redemption(1, "6", "reward");
+        canceled.status = "canceled".to_string();
+        canceled.create(&conn).await.unwrap();
+
+        // act
+        let res = Redemption::leaderboard(&conn, "reward", 10, 0).await;
+
+        // assert
+        assert!(res.is_ok());
+        let entries = res.unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                RewardLeaderboardEntry {
+                    name: "arinonono".to_string(),
+                    redemptions: 2,
+                    points: 200,
+                },
+                RewardLeaderboardEntry {
+                    name: "arinono".to_string(),
+                    redemptions: 1,
+                    points: 100,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{params, query::Model, Orm, OrmBase, OrmError};
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Model, OrmBase)]
+#[orm(table = "resubs", validate)]
+pub struct Resub {
+    pub id: u64,
+    pub user_id: Option<u64>,
+    pub tier: String,
+    pub cumulative_months: u32,
+    pub streak_months: Option<u32>,
+    pub duration_months: u32,
+    pub message: Option<String>,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime<Utc>,
+    pub stream_id: Option<u64>,
+}
+
+impl Default for Resub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resub {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self {
+            id: 0,
+            user_id: None,
+            tier: String::new(),
+            cumulative_months: 0,
+            streak_months: None,
+            duration_months: 0,
+            message: None,
+            created_at: Utc::now(),
+            stream_id: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from(user_id: u64, tier: String, cumulative_months: u32, duration_months: u32) -> Self {
+        Self {
+            id: 0,
+            user_id: Some(user_id),
+            tier,
+            cumulative_months,
+            streak_months: None,
+            duration_months,
+            message: None,
+            created_at: Utc::now(),
+            stream_id: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self) -> Result<(), OrmError> {
+        if self.user_id.is_none() {
+            return Err(OrmError::BadInput("Resub requires a user".to_string()));
+        }
+
+        match self.tier.as_str() {
+            "Tier1" | "Tier2" | "Tier3" | "Prime" | "Other" => Ok(()),
+            _ => Err(OrmError::BadInput("Invalid sub tier name".to_string())),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn list(
+        conn: &libsql::Connection,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Self>, OrmError> {
+        let query = "select * from resubs
+            order by id desc
+            limit ?1 offset ?2
+        ";
+        let params = params![limit, offset];
+
+        Orm::<Resub>::query(conn, query, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{timestamp, user::User, OrmBase};
+
+    use super::*;
+    use chrono::TimeZone;
+    use libsql::{de, Builder, Connection};
+    use rand::{distributions::Alphanumeric, Rng};
+    use tracing_test::traced_test;
+
+    async fn conn(with_user: bool) -> Connection {
+        let rand: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        let db_name = format!("test_{}.sqlite", rand.to_lowercase());
+        std::fs::copy("tests.sqlite", &db_name).unwrap();
+
+        let db = Builder::new_local(&db_name).build().await.unwrap();
+        let conn = db.connect().unwrap();
+
+        if with_user {
+            let user = User::from("arinono".to_string(), 42069);
+            user.create(&conn).await.unwrap();
+        }
+
+        println!("Running test on {}", db_name);
+
+        conn
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors_user() {
+        // arrange
+        let conn = conn(true).await;
+        let mut resub = Resub::new();
+        resub.tier = "Tier1".to_string();
+
+        // act
+        let res = resub.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Resub requires a user".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors_tier() {
+        // arrange
+        let conn = conn(true).await;
+        let resub = Resub::from(1, "Tier4".to_string(), 2, 1);
+
+        // act
+        let res = resub.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Invalid sub tier name".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_no_user() {
+        // arrange
+        let conn = conn(false).await;
+        let resub = Resub::from(1, "Tier1".to_string(), 2, 1);
+
+        // act
+        let res = resub.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::NoChange("No resub created".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create() {
+        // arrange
+        let conn = conn(true).await;
+        let mut resub = Resub::from(1, "Tier2".to_string(), 12, 3);
+        resub.streak_months = Some(5);
+        resub.message = Some("a year already".to_string());
+
+        // act
+        let res = resub.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 1);
+
+        let mut rows = conn
+            .query("select * from resubs where id = ?1 limit 1", [1])
+            .await
+            .unwrap();
+        let row = rows.next().await.unwrap().unwrap();
+        let resub_st = de::from_row::<Resub>(&row).unwrap();
+
+        assert_eq!(resub_st.id, 1);
+        assert_eq!(resub_st.user_id, Some(1));
+        assert_eq!(resub_st.tier, "Tier2".to_string());
+        assert_eq!(resub_st.cumulative_months, 12);
+        assert_eq!(resub_st.streak_months, Some(5));
+        assert_eq!(resub_st.duration_months, 3);
+        assert_eq!(resub_st.message, Some("a year already".to_string()));
+        assert!(resub_st.created_at <= Utc::now());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_created_at() {
+        // arrange
+        let conn = conn(true).await;
+        let mut resub = Resub::from(1, "Tier1".to_string(), 2, 1);
+        resub.created_at = Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap();
+
+        // act
+        let res = resub.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        let mut rows = conn
+            .query("select * from resubs where id = ?1 limit 1", [res.unwrap()])
+            .await
+            .unwrap();
+        let row = rows.next().await.unwrap().unwrap();
+        let resub_st = de::from_row::<Resub>(&row).unwrap();
+        assert_eq!(
+            timestamp::format(&resub_st.created_at),
+            "2025-02-10T20:00:00.000Z".to_string()
+        );
+        assert_eq!(resub_st.streak_months, None);
+        assert_eq!(resub_st.message, None);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn list() {
+        // arrange
+        let conn = conn(true).await;
+        Resub::from(1, "Tier1".to_string(), 2, 1)
+            .create(&conn)
+            .await
+            .unwrap();
+        Resub::from(1, "Tier1".to_string(), 3, 1)
+            .create(&conn)
+            .await
+            .unwrap();
+
+        // act
+        let res = Resub::list(&conn, 10, 0).await;
+
+        // assert
+        assert!(res.is_ok());
+        let resubs = res.unwrap();
+        assert_eq!(resubs.len(), 2);
+        assert_eq!(resubs[0].cumulative_months, 3);
+        assert_eq!(resubs[1].cumulative_months, 2);
+    }
+}
@@ -0,0 +1,314 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    params,
+    query::{Insert, Model},
+    Orm, OrmError, RowId,
+};
+
+/// A live session, opened by `stream.online` and closed by `stream.offline`.
+/// At most one stream is expected to be active (without `ended_at`) at a time.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Model)]
+#[orm(table = "streams")]
+pub struct Stream {
+    pub id: u64,
+    pub stream_id: String,
+    pub stream_type: String,
+    #[serde(with = "crate::timestamp")]
+    pub started_at: DateTime<Utc>,
+    #[serde(with = "crate::timestamp::option")]
+    pub ended_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Default for Stream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stream {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self {
+            id: 0,
+            stream_id: String::new(),
+            stream_type: String::new(),
+            started_at: Utc::now(),
+            ended_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn from(stream_id: String, stream_type: String, started_at: DateTime<Utc>) -> Self {
+        Self {
+            stream_id,
+            stream_type,
+            started_at,
+            ..Self::new()
+        }
+    }
+
+    #[allow(dead_code)]
+    fn validate(&self) -> Result<(), OrmError> {
+        if self.stream_id.is_empty() {
+            return Err(OrmError::BadInput(
+                "Stream requires a stream id".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Fails with `OrmError::NoChange` when the stream was already recorded.
+    #[allow(dead_code)]
+    pub async fn create(&self, conn: &libsql::Connection) -> Result<u64, OrmError> {
+        self.validate()?;
+
+        let (query, params) = Insert::model(self)
+            .now("created_at")
+            .on_conflict("(stream_id) do nothing")
+            .build();
+
+        let rows = Orm::<RowId>::query(conn, &query, params).await?;
+
+        match rows.first() {
+            None => Err(OrmError::NoChange("No stream created".to_string())),
+            Some(row) => Ok(row.id),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn get(conn: &libsql::Connection, id: u64) -> Result<Option<Self>, OrmError> {
+        let query = "select * from streams
+            where id = ?1
+            limit 1
+        ";
+        let params = params![id];
+
+        let rows = Orm::<Stream>::query(conn, query, params).await?;
+
+        Ok(rows.first().cloned())
+    }
+
+    /// The most recently started stream that has not ended yet.
+    #[allow(dead_code)]
+    pub async fn active(conn: &libsql::Connection) -> Result<Option<Self>, OrmError> {
+        let query = "select * from streams
+            where ended_at is null
+            order by started_at desc, id desc
+            limit 1
+        ";
+
+        let rows = Orm::<Stream>::query(conn, query, params![]).await?;
+
+        Ok(rows.first().cloned())
+    }
+
+    /// Closes every active stream and returns the most recent of them.
+    #[allow(dead_code)]
+    pub async fn end(
+        conn: &libsql::Connection,
+        ended_at: DateTime<Utc>,
+    ) -> Result<Option<Self>, OrmError> {
+        let active = Stream::active(conn).await?;
+
+        let query = "update streams set ended_at = ?1 where ended_at is null";
+        Orm::<Stream>::execute(conn, query, params![ended_at]).await?;
+
+        match active {
+            None => Ok(None),
+            Some(stream) => Stream::get(conn, stream.id).await,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn list(
+        conn: &libsql::Connection,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Self>, OrmError> {
+        let query = "select * from streams
+            order by started_at desc, id desc
+            limit ?1 offset ?2
+        ";
+        let params = params![limit, offset];
+
+        Orm::<Stream>::query(conn, query, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp;
+    use libsql::{Builder, Connection};
+    use rand::{distributions::Alphanumeric, Rng};
+    use tracing_test::traced_test;
+
+    async fn conn() -> Connection {
+        let rand: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        let db_name = format!("test_{}.sqlite", rand.to_lowercase());
+        std::fs::copy("tests.sqlite", &db_name).unwrap();
+
+        let db = Builder::new_local(&db_name).build().await.unwrap();
+        let conn = db.connect().unwrap();
+
+        println!("Running test on {}", db_name);
+
+        conn
+    }
+
+    fn stream(stream_id: &str, started_at: &str) -> Stream {
+        Stream::from(
+            stream_id.to_string(),
+            "live".to_string(),
+            timestamp::parse(started_at).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_errors() {
+        // arrange
+        let conn = conn().await;
+        let stream = stream("", "2025-02-10T20:00:00Z");
+
+        // act
+        let res = stream.create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::BadInput("Stream requires a stream id".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create() {
+        // arrange
+        let conn = conn().await;
+        let stream = stream("1234", "2025-02-10T20:00:00Z");
+
+        // act
+        let res = stream.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 1);
+
+        let stream_st = Stream::get(&conn, 1).await.unwrap().unwrap();
+        assert_eq!(stream_st.stream_id, "1234".to_string());
+        assert_eq!(stream_st.stream_type, "live".to_string());
+        assert_eq!(
+            timestamp::format(&stream_st.started_at),
+            "2025-02-10T20:00:00.000Z".to_string()
+        );
+        assert_eq!(stream_st.ended_at, None);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_duplicate_stream_id() {
+        // arrange
+        let conn = conn().await;
+        stream("1234", "2025-02-10T20:00:00Z")
+            .create(&conn)
+            .await
+            .unwrap();
+
+        // act
+        let res = stream("1234", "2025-02-10T20:00:00Z").create(&conn).await;
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            OrmError::NoChange("No stream created".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn active() {
+        // arrange
+        let conn = conn().await;
+
+        // act
+        let none = Stream::active(&conn).await;
+
+        // assert
+        assert!(none.is_ok());
+        assert!(none.unwrap().is_none());
+
+        // arrange
+        stream("1", "2025-02-10T20:00:00Z")
+            .create(&conn)
+            .await
+            .unwrap();
+        stream("2", "2025-02-11T20:00:00Z")
+            .create(&conn)
+            .await
+            .unwrap();
+
+        // act
+        let active = Stream::active(&conn).await;
+
+        // assert
+        assert!(active.is_ok());
+        assert_eq!(active.unwrap().unwrap().stream_id, "2".to_string());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn end() {
+        // arrange
+        let conn = conn().await;
+        stream("1", "2025-02-10T20:00:00Z")
+            .create(&conn)
+            .await
+            .unwrap();
+        stream("2", "2025-02-11T20:00:00Z")
+            .create(&conn)
+            .await
+            .unwrap();
+
+        // act
+        let res = Stream::end(&conn, timestamp::parse("2025-02-11T23:00:00Z").unwrap()).await;
+
+        // assert
+        assert!(res.is_ok());
+        let ended = res.unwrap().unwrap();
+        assert_eq!(ended.stream_id, "2".to_string());
+        assert_eq!(
+            ended.ended_at.map(|at| timestamp::format(&at)),
+            Some("2025-02-11T23:00:00.000Z".to_string())
+        );
+        assert!(Stream::active(&conn).await.unwrap().is_none());
+        let first = Stream::get(&conn, 1).await.unwrap().unwrap();
+        assert_eq!(first.ended_at, ended.ended_at);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn end_without_active() {
+        // arrange
+        let conn = conn().await;
+
+        // act
+        let res = Stream::end(&conn, timestamp::parse("2025-02-11T23:00:00Z").unwrap()).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert!(res.unwrap().is_none());
+    }
+}
@@ -1,14 +1,18 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
-use super::{user::User, Orm, OrmBase, OrmError, RowId, SQL_NOW_UTC_ISO};
+use crate::{params, query::Model, Orm, OrmBase, OrmError};
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Model, OrmBase)]
+#[orm(table = "subgifts", validate)]
 pub struct Subgift {
     pub id: u64,
     pub user_id: Option<u64>,
     pub number: u16,
     pub tier: String,
-    pub created_at: String,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime<Utc>,
+    pub stream_id: Option<u64>,
 }
 
 impl Default for Subgift {
@@ -25,7 +29,8 @@ impl Subgift {
             user_id: None,
             number: 0,
             tier: String::new(),
-            created_at: String::new(),
+            created_at: Utc::now(),
+            stream_id: None,
         }
     }
 
@@ -36,7 +41,8 @@ impl Subgift {
             user_id: Some(user_id),
             number,
             tier,
-            created_at: String::new(),
+            created_at: Utc::now(),
+            stream_id: None,
         }
     }
 
@@ -47,7 +53,8 @@ impl Subgift {
             user_id: None,
             number,
             tier,
-            created_at: String::new(),
+            created_at: Utc::now(),
+            stream_id: None,
         }
     }
 
@@ -66,44 +73,27 @@ impl Subgift {
     }
 
     #[allow(dead_code)]
-    pub async fn create(&self, conn: &libsql::Connection) -> Result<u64, OrmError> {
-        self.validate()?;
-
-        let mut columns: Vec<String> = vec!["number".to_string(), "tier".to_string()];
-        let mut replacements = vec![self.number.to_string(), self.tier.clone()];
-
-        if let Some(id) = self.user_id {
-            User::get(conn, id).await?;
-            columns.push("user_id".to_string());
-            replacements.push(id.to_string());
-        }
-
-        let query = format!(
-            "insert into subgifts (
-                {}, created_at
-            ) values (
-                {}, {}
-            ) returning id",
-            columns.join(", "),
-            Orm::<Subgift>::placeholders(columns.len()),
-            SQL_NOW_UTC_ISO
-        );
-
-        let rows = Orm::<RowId>::query(conn, &query, replacements).await?;
-
-        match rows.first() {
-            None => Err(OrmError::NoChange("No subgift created".to_string())),
-            Some(row) => Ok(row.id),
-        }
+    pub async fn list(
+        conn: &libsql::Connection,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Self>, OrmError> {
+        let query = "select * from subgifts
+            order by id desc
+            limit ?1 offset ?2
+        ";
+        let params = params![limit, offset];
+
+        Orm::<Subgift>::query(conn, query, params).await
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{user::User, OrmBase, CHRONO_UTC_ISO_FMT};
+    use crate::{user::User, OrmBase};
 
     use super::*;
-    use chrono::NaiveDateTime;
+    use chrono::TimeZone;
     use libsql::{de, Builder, Connection};
     use rand::{distributions::Alphanumeric, Rng};
     use tracing_test::traced_test;
@@ -208,7 +198,58 @@ mod tests {
         assert_eq!(subgift_st.id, 1);
         assert_eq!(subgift_st.user_id, Some(1));
         assert_eq!(subgift_st.number, 1);
-        let created_at = NaiveDateTime::parse_from_str(&subgift_st.created_at, CHRONO_UTC_ISO_FMT);
-        assert!(created_at.is_ok());
+        assert!(subgift_st.created_at <= Utc::now());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_created_at() {
+        // arrange
+        let conn = conn(true).await;
+        let mut subgift = Subgift::from(1, 5, "Tier1".to_string());
+        subgift.created_at = Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap();
+
+        // act
+        let res = subgift.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        let mut rows = conn
+            .query(
+                "select created_at from subgifts where id = ?1 limit 1",
+                [res.unwrap()],
+            )
+            .await
+            .unwrap();
+        let row = rows.next().await.unwrap().unwrap();
+        assert_eq!(
+            row.get::<String>(0).unwrap(),
+            "2025-02-10T20:00:00.000Z".to_string()
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn list() {
+        // arrange
+        let conn = conn(true).await;
+        Subgift::from(1, 1, "Tier1".to_string())
+            .create(&conn)
+            .await
+            .unwrap();
+        Subgift::from_anonymous(5, "Tier2".to_string())
+            .create(&conn)
+            .await
+            .unwrap();
+
+        // act
+        let res = Subgift::list(&conn, 1, 1).await;
+
+        // assert
+        assert!(res.is_ok());
+        let subgifts = res.unwrap();
+        assert_eq!(subgifts.len(), 1);
+        assert_eq!(subgifts[0].number, 1);
+        assert_eq!(subgifts[0].tier, "Tier1".to_string());
     }
 }
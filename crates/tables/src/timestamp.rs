@@ -0,0 +1,89 @@
+//! Timestamps are stored as text in a single format, the one
+//! `SQL_NOW_UTC_ISO` produces (`2025-02-10T20:00:00.000Z`), so they sort the
+//! same as text and as time. Use with `#[serde(with = "crate::timestamp")]`
+//! on `DateTime<Utc>` fields, or `crate::timestamp::option` on optional ones.
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+pub fn format(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Reads RFC 3339 with any offset and precision, and SQLite's own
+/// `YYYY-MM-DD HH:MM:SS` which is UTC.
+pub fn parse(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|at| at.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+                .map(|at| at.and_utc())
+                .ok()
+        })
+}
+
+pub fn serialize<S: Serializer>(at: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(at))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+
+    parse(&text).ok_or_else(|| D::Error::custom(format!("invalid timestamp `{}`", text)))
+}
+
+pub mod option {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        at: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match at {
+            Some(at) => super::serialize(at, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        use serde::de::Error;
+
+        match Option::<String>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(text) => super::parse(&text)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("invalid timestamp `{}`", text))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    #[test]
+    #[traced_test]
+    fn parse_formats() {
+        // arrange
+        let expected = "2025-02-10T20:00:00.123Z".to_string();
+
+        // act
+        let res = [
+            "2025-02-10T20:00:00.123Z",
+            "2025-02-10T20:00:00.123456789+00:00",
+            "2025-02-10T21:00:00.123+01:00",
+            "2025-02-10 20:00:00.123",
+        ]
+        .map(|text| parse(text).map(|at| format(&at)));
+
+        // assert
+        assert!(res.iter().all(|at| at.as_ref() == Some(&expected)));
+        assert!(parse("yesterday").is_none());
+    }
+}
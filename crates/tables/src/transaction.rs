@@ -0,0 +1,135 @@
+use std::ops::Deref;
+
+use libsql::{Connection, TransactionBehavior};
+
+use super::OrmError;
+
+/// Runs several statements atomically. It derefs to the connection, so the
+/// `Orm` helpers and `OrmBase` methods work inside it unchanged:
+///
+/// ```ignore
+/// let tx = Transaction::begin(&conn).await?;
+/// let user_id = user.create(&tx).await?;
+/// Subgift::from(user_id, 5, "Tier1".to_string()).create(&tx).await?;
+/// tx.commit().await?;
+/// ```
+///
+/// It takes the write lock as it begins, so it never fails half-way because
+/// another connection wrote since it first read. A transaction dropped
+/// without `commit` is rolled back by local databases only, call `rollback`
+/// to give up on it everywhere.
+pub struct Transaction {
+    inner: libsql::Transaction,
+}
+
+impl Transaction {
+    pub async fn begin(conn: &Connection) -> Result<Self, OrmError> {
+        let inner = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .await
+            .map_err(|e| {
+                tracing::error!(kind = "transaction", error = %e, "Failed to begin transaction");
+                OrmError::from(e)
+            })?;
+
+        Ok(Self { inner })
+    }
+
+    pub async fn commit(self) -> Result<(), OrmError> {
+        self.inner.commit().await.map_err(|e| {
+            tracing::error!(kind = "transaction", error = %e, "Failed to commit transaction");
+            OrmError::from(e)
+        })
+    }
+
+    pub async fn rollback(self) -> Result<(), OrmError> {
+        tracing::warn!(kind = "transaction", "Rolling back transaction");
+
+        self.inner.rollback().await.map_err(|e| {
+            tracing::error!(kind = "transaction", error = %e, "Failed to roll back transaction");
+            OrmError::from(e)
+        })
+    }
+}
+
+impl Deref for Transaction {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libsql::{Builder, Connection};
+    use rand::{distributions::Alphanumeric, Rng};
+    use tracing_test::traced_test;
+
+    use crate::{bits::Bit, user::User, OrmBase};
+
+    use super::*;
+
+    async fn conn() -> Connection {
+        let rand: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        let db_name = format!("test_{}.sqlite", rand.to_lowercase());
+        std::fs::copy("tests.sqlite", &db_name).unwrap();
+
+        let db = Builder::new_local(&db_name).build().await.unwrap();
+        let conn = db.connect().unwrap();
+
+        println!("Running test on {}", db_name);
+
+        conn
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn commit() {
+        // arrange
+        let conn = conn().await;
+        let tx = Transaction::begin(&conn).await.unwrap();
+        let user_id = User::from("arinono".to_string(), 42069)
+            .create(&tx)
+            .await
+            .unwrap();
+        let bit_id = Bit::from(user_id, 100, None).create(&tx).await.unwrap();
+
+        // act
+        let res = tx.commit().await;
+
+        // assert
+        assert!(res.is_ok());
+        assert!(User::get(&conn, user_id).await.unwrap().is_some());
+        assert!(Bit::get(&conn, bit_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn rollback() {
+        // arrange
+        let conn = conn().await;
+        let tx = Transaction::begin(&conn).await.unwrap();
+        let user_id = User::from("arinono".to_string(), 42069)
+            .create(&tx)
+            .await
+            .unwrap();
+        let bit = Bit::from(user_id, 0, None).create(&tx).await;
+        assert!(bit.is_err());
+
+        // act
+        let res = tx.rollback().await;
+
+        // assert
+        assert!(res.is_ok());
+        assert!(User::get(&conn, user_id).await.unwrap().is_none());
+        assert!(User::get_by_twitch_id(&conn, 42069)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
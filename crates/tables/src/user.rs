@@ -1,21 +1,129 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
-use crate::{add_if_present, Orm, SQL_NOW_UTC_ISO};
+use crate::{
+    params,
+    query::{Column, Insert, Model, Update},
+    Orm, SQL_NOW_UTC_ISO,
+};
 
 use super::{OrmBase, OrmError, RowId};
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Model, OrmBase)]
+#[orm(table = "users", validate)]
 pub struct User {
     pub id: u64,
     pub display_name: String,
     pub twitch_id: u64,
-    pub follower_since: Option<String>,
-    pub subscriber_since: Option<String>,
+    #[serde(with = "crate::timestamp::option")]
+    pub follower_since: Option<DateTime<Utc>>,
+    #[serde(with = "crate::timestamp::option")]
+    pub subscriber_since: Option<DateTime<Utc>>,
     pub subgift_total: Option<usize>,
     pub subscription_tier: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
-    pub deleted_at: Option<String>,
+    #[serde(with = "crate::timestamp")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::timestamp")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(with = "crate::timestamp::option")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[orm(skip)]
+    #[serde(with = "crate::timestamp::option")]
+    pub suspected_at: Option<DateTime<Utc>>,
+    #[orm(skip)]
+    pub login: Option<String>,
+    #[orm(skip)]
+    #[serde(with = "crate::timestamp::option")]
+    pub account_created_at: Option<DateTime<Utc>>,
+    #[orm(skip)]
+    pub profile_image_url: Option<String>,
+    #[orm(skip)]
+    pub broadcaster_type: Option<String>,
+    #[orm(skip)]
+    #[serde(with = "crate::timestamp::option")]
+    pub enriched_at: Option<DateTime<Utc>>,
+}
+
+/// What Helix knows about a user, see `User::set_profile`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct UserProfile {
+    pub login: String,
+    #[serde(with = "crate::timestamp")]
+    pub account_created_at: DateTime<Utc>,
+    pub profile_image_url: Option<String>,
+    pub broadcaster_type: String,
+}
+
+/// What `User::upsert_by_twitch_id` changes besides the display name. Fields
+/// left to `None` keep their stored value, `Some(None)` clears them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserPatch {
+    pub follower_since: Option<Option<DateTime<Utc>>>,
+    pub subscriber_since: Option<Option<DateTime<Utc>>>,
+    pub subscription_tier: Option<Option<String>>,
+    /// Only sets `subscriber_since` on users without one.
+    pub keep_subscriber_since: bool,
+}
+
+impl UserPatch {
+    #[allow(dead_code)]
+    pub fn follow(at: DateTime<Utc>) -> Self {
+        Self {
+            follower_since: Some(Some(at)),
+            ..Self::default()
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn subscribe(at: DateTime<Utc>, tier: String) -> Self {
+        Self {
+            subscriber_since: Some(Some(at)),
+            subscription_tier: Some(Some(tier)),
+            ..Self::default()
+        }
+    }
+
+    /// A resub changes the tier but not when the subscription started.
+    #[allow(dead_code)]
+    pub fn resubscribe(at: DateTime<Utc>, tier: String) -> Self {
+        Self {
+            keep_subscriber_since: true,
+            ..Self::subscribe(at, tier)
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn unsubscribe() -> Self {
+        Self {
+            subscriber_since: Some(None),
+            subscription_tier: Some(None),
+            ..Self::default()
+        }
+    }
+
+    fn validate(&self) -> Result<(), OrmError> {
+        if let Some(Some(tier)) = &self.subscription_tier {
+            User::validate_tier(tier.clone())?;
+        }
+
+        match (&self.subscriber_since, &self.subscription_tier) {
+            (Some(Some(_)), Some(Some(_))) | (Some(None), Some(None)) | (None, None) => Ok(()),
+            _ => Err(OrmError::BadInput(
+                "subscriber_since and subscription_tier must be patched together".to_string(),
+            )),
+        }
+    }
+
+    fn columns(&self) -> Vec<Column> {
+        [
+            Column::optional("follower_since", &self.follower_since),
+            Column::optional("subscriber_since", &self.subscriber_since),
+            Column::optional("subscription_tier", &self.subscription_tier),
+        ]
+        .into_iter()
+        .filter(|column| column.value.is_some())
+        .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -23,13 +131,13 @@ pub struct UserBuilder(pub User);
 
 impl UserBuilder {
     #[allow(dead_code)]
-    pub fn follow(mut self, date: String) -> Self {
+    pub fn follow(mut self, date: DateTime<Utc>) -> Self {
         self.0.follower_since = Some(date);
         self
     }
 
     #[allow(dead_code)]
-    pub fn subscribe(mut self, date: String) -> Self {
+    pub fn subscribe(mut self, date: DateTime<Utc>) -> Self {
         self.0.subscriber_since = Some(date);
         self
     }
@@ -41,7 +149,7 @@ impl UserBuilder {
     }
 
     #[allow(dead_code)]
-    pub fn created_at(mut self, created_at: String) -> Self {
+    pub fn created_at(mut self, created_at: DateTime<Utc>) -> Self {
         self.0.created_at = created_at;
         self
     }
@@ -52,7 +160,7 @@ impl UserBuilder {
             self.0.subscription_tier = Some("Tier1".to_string());
         }
         if self.0.subscription_tier.is_some() && self.0.subscriber_since.is_none() {
-            self.0.subscriber_since = Some(Orm::<()>::now_utc());
+            self.0.subscriber_since = Some(Utc::now());
         }
         self.0.clone()
     }
@@ -75,9 +183,15 @@ impl User {
             subscriber_since: None,
             subscription_tier: None,
             subgift_total: None,
-            created_at: String::new(),
-            updated_at: String::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
             deleted_at: None,
+            suspected_at: None,
+            login: None,
+            account_created_at: None,
+            profile_image_url: None,
+            broadcaster_type: None,
+            enriched_at: None,
         }
     }
 
@@ -91,9 +205,15 @@ impl User {
             subscriber_since: None,
             subscription_tier: None,
             subgift_total: None,
-            created_at: String::new(),
-            updated_at: String::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
             deleted_at: None,
+            suspected_at: None,
+            login: None,
+            account_created_at: None,
+            profile_image_url: None,
+            broadcaster_type: None,
+            enriched_at: None,
         }
     }
 
@@ -102,6 +222,18 @@ impl User {
         UserBuilder(User::from(display_name.clone(), twitch_id))
     }
 
+    /// The Helix profile, once `set_profile` stored one.
+    pub fn profile(&self) -> Option<UserProfile> {
+        self.enriched_at.as_ref()?;
+
+        Some(UserProfile {
+            login: self.login.clone()?,
+            account_created_at: self.account_created_at?,
+            profile_image_url: self.profile_image_url.clone(),
+            broadcaster_type: self.broadcaster_type.clone().unwrap_or_default(),
+        })
+    }
+
     fn validate_tier(tier: String) -> Result<(), OrmError> {
         match tier.as_str() {
             "Tier1" | "Tier2" | "Tier3" | "Prime" | "Other" => Ok(()),
@@ -139,9 +271,9 @@ impl User {
                 and deleted_at is null
             limit 1
         ";
-        let replacements = vec![id.to_string()];
+        let params = params![id];
 
-        let rows = Orm::<User>::query(conn, &query.to_string(), replacements).await?;
+        let rows = Orm::<User>::query(conn, query, params).await?;
 
         if rows.len() != 1 {
             return Ok(None);
@@ -149,127 +281,154 @@ impl User {
 
         Ok(Some(rows[0].clone()))
     }
-}
-
-impl OrmBase<User> for User {
-    async fn create(&self, conn: &libsql::Connection) -> Result<u64, OrmError> {
-        self.validate()?;
-
-        let mut columns = vec!["display_name", "twitch_id"];
-        let mut replacements = vec![self.display_name.clone(), self.twitch_id.to_string()];
-
-        add_if_present!(columns, replacements, self, follower_since);
-        add_if_present!(columns, replacements, self, subscriber_since);
-        add_if_present!(columns, replacements, self, subgift_total);
-        add_if_present!(columns, replacements, self, subscription_tier);
-        add_if_present!(columns, replacements, self, follower_since);
-
-        let query = format!(
-            "insert into users (
-                {}, created_at, updated_at
-            ) values (
-                {}, {}, {}
-            ) returning id",
-            columns.join(", "),
-            Orm::<User>::placeholders(columns.len()),
-            SQL_NOW_UTC_ISO,
-            SQL_NOW_UTC_ISO
-        );
 
-        let rows = Orm::<RowId>::query(conn, &query, replacements).await?;
+    /// Creates the user or updates the one with the same Twitch id, in a
+    /// single statement so concurrent events for a new user cannot both
+    /// insert it. `at` is the `created_at` of a new user. Fails with
+    /// `OrmError::NoChange` when the user was deleted.
+    #[allow(dead_code)]
+    pub async fn upsert_by_twitch_id(
+        conn: &libsql::Connection,
+        twitch_id: u64,
+        display_name: &str,
+        patch: &UserPatch,
+        at: &DateTime<Utc>,
+    ) -> Result<u64, OrmError> {
+        patch.validate()?;
+
+        let columns = patch.columns();
+        let updates = ["display_name", "updated_at"]
+            .into_iter()
+            .chain(columns.iter().map(|column| column.name))
+            .map(|column| match column {
+                "subscriber_since" if patch.keep_subscriber_since => {
+                    "subscriber_since = coalesce(users.subscriber_since, excluded.subscriber_since)"
+                        .to_string()
+                }
+                _ => format!("{} = excluded.{}", column, column),
+            })
+            .collect::<Vec<String>>();
+
+        let (query, params) = Insert::into(User::TABLE)
+            .value("display_name", display_name)
+            .value("twitch_id", twitch_id)
+            .columns(columns)
+            .timestamp("created_at", at)
+            .now("updated_at")
+            .on_conflict(&format!(
+                "(twitch_id) do update set {} where users.deleted_at is null",
+                updates.join(", ")
+            ))
+            .build();
+
+        let rows = Orm::<RowId>::query(conn, &query, params).await?;
 
         match rows.first() {
-            None => Err(OrmError::NoChange("No user created".to_string())),
+            None => Err(OrmError::NoChange(format!("User {} is deleted", twitch_id))),
             Some(row) => Ok(row.id),
         }
     }
 
-    async fn get(conn: &libsql::Connection, id: u64) -> Result<Option<Self>, OrmError> {
+    #[allow(dead_code)]
+    pub async fn list(
+        conn: &libsql::Connection,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Self>, OrmError> {
         let query = "select * from users
-            where id = ?1
-                and deleted_at is null
-            limit 1
-            ";
-        let replacements: Vec<String> = vec![id.to_string()];
+            where deleted_at is null
+            order by id desc
+            limit ?1 offset ?2
+        ";
+        let params = params![limit, offset];
 
-        let rows = Orm::<User>::query(conn, &query.to_string(), replacements).await?;
+        Orm::<User>::query(conn, query, params).await
+    }
 
-        if rows.len() != 1 {
-            return Ok(None);
-        }
+    /// Marks the user as a likely bot, e.g. one that followed during a
+    /// follow-bot wave. Already flagged users keep their first flag date.
+    #[allow(dead_code)]
+    pub async fn flag_suspected(conn: &libsql::Connection, id: u64) -> Result<(), OrmError> {
+        let (query, params) = Update::table(User::TABLE)
+            .expr(
+                "suspected_at",
+                &format!("coalesce(suspected_at, {})", SQL_NOW_UTC_ISO),
+            )
+            .filter("id", id)
+            .filter_sql("deleted_at is null")
+            .build();
+
+        Orm::<User>::execute(conn, &query, params).await?;
 
-        Ok(Some(rows[0].clone()))
+        Ok(())
     }
 
-    async fn update(&mut self, conn: &libsql::Connection) -> Result<(), OrmError> {
-        if User::get(conn, self.id).await?.is_none() {
-            return Err(OrmError::NotFound("update user".to_string(), Some(self.id)));
+    /// Soft-deletes the suspected users among `ids`, or every suspected user
+    /// when `ids` is `None`, in a single statement. Users that are not flagged
+    /// are left alone. Returns how many were deleted.
+    #[allow(dead_code)]
+    pub async fn delete_suspected(
+        conn: &libsql::Connection,
+        ids: Option<&[u64]>,
+    ) -> Result<u64, OrmError> {
+        let mut update = Update::table(User::TABLE)
+            .now("deleted_at")
+            .filter_sql("deleted_at is null")
+            .filter_sql("suspected_at is not null");
+        if let Some(ids) = ids {
+            update = update.filter_in("id", ids);
         }
+        let (query, params) = update.build();
 
-        self.validate()?;
-
-        let mut columns = vec!["display_name"];
-        let mut replacements = vec![self.display_name.clone()];
-
-        add_if_present!(columns, replacements, self, follower_since);
-        add_if_present!(columns, replacements, self, subscriber_since);
-        add_if_present!(columns, replacements, self, subgift_total);
-        add_if_present!(columns, replacements, self, subscription_tier);
-        add_if_present!(columns, replacements, self, follower_since);
-
-        let query = format!(
-            "update users set
-                updated_at = {},
-                {}
-            where id = ?{} and deleted_at is null",
-            SQL_NOW_UTC_ISO,
-            Orm::<User>::update_placeholders(&columns),
-            columns.len() + 1,
-        );
-        replacements.push(self.id.to_string());
-
-        Orm::<User>::execute(conn, &query, replacements).await?;
-
-        let user_st = User::get(conn, self.id).await?.unwrap();
-        self.id = user_st.id;
-        self.twitch_id = user_st.twitch_id;
-        self.display_name = user_st.display_name.clone();
-        self.follower_since = user_st.follower_since;
-        self.subscriber_since = user_st.subscriber_since;
-        self.subscription_tier = user_st.subscription_tier;
-        self.subgift_total = user_st.subgift_total;
-        self.created_at = user_st.created_at;
-        self.updated_at = user_st.updated_at;
-        self.deleted_at = user_st.deleted_at;
-
-        Ok(())
+        Orm::<User>::execute(conn, &query, params).await
     }
 
-    async fn delete(&self, conn: &libsql::Connection) -> Result<(), OrmError> {
-        if User::get(conn, self.id).await?.is_none() {
-            return Err(OrmError::NotFound("delete user".to_string(), Some(self.id)));
-        };
+    /// Stores the Helix profile and stamps `enriched_at`, which tells apart
+    /// the users that still need a lookup.
+    #[allow(dead_code)]
+    pub async fn set_profile(
+        conn: &libsql::Connection,
+        id: u64,
+        profile: &UserProfile,
+    ) -> Result<(), OrmError> {
+        let (query, params) = Update::table(User::TABLE)
+            .set("login", &profile.login)
+            .set("account_created_at", profile.account_created_at)
+            .set("profile_image_url", &profile.profile_image_url)
+            .set("broadcaster_type", &profile.broadcaster_type)
+            .now("enriched_at")
+            .now("updated_at")
+            .filter("id", id)
+            .filter_sql("deleted_at is null")
+            .build();
+
+        Orm::<User>::execute(conn, &query, params).await?;
 
-        let query = format!(
-            "update users
-                set deleted_at = {}
-            where id = ?1 and deleted_at is null",
-            SQL_NOW_UTC_ISO,
-        );
-        let replacements: Vec<String> = vec![self.id.to_string()];
+        Ok(())
+    }
 
-        Orm::<User>::execute(conn, &query.to_string(), replacements).await?;
+    #[allow(dead_code)]
+    pub async fn list_suspected(
+        conn: &libsql::Connection,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<Self>, OrmError> {
+        let query = "select * from users
+            where deleted_at is null
+                and suspected_at is not null
+            order by id desc
+            limit ?1 offset ?2
+        ";
+        let params = params![limit, offset];
 
-        Ok(())
+        Orm::<User>::query(conn, query, params).await
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::CHRONO_UTC_ISO_FMT;
-
     use super::*;
-    use chrono::{NaiveDateTime, Utc};
+    use chrono::{SubsecRound, TimeZone};
     use libsql::{de, Builder, Connection};
     use rand::{distributions::Alphanumeric, Rng};
     use tracing_test::traced_test;
@@ -319,19 +478,9 @@ mod tests {
         assert_eq!(user_st.subscriber_since, None);
         assert_eq!(user_st.subgift_total, None);
 
-        let created_at = NaiveDateTime::parse_from_str(&user_st.created_at, CHRONO_UTC_ISO_FMT);
-        assert!(created_at.is_ok());
-        let updated_at = NaiveDateTime::parse_from_str(&user_st.updated_at, CHRONO_UTC_ISO_FMT);
-        assert!(updated_at.is_ok());
         let now = Utc::now();
-        assert_eq!(
-            now.timestamp() - created_at.unwrap().and_utc().timestamp(),
-            0
-        );
-        assert_eq!(
-            now.timestamp() - updated_at.unwrap().and_utc().timestamp(),
-            0
-        );
+        assert_eq!(now.timestamp() - user_st.created_at.timestamp(), 0);
+        assert_eq!(now.timestamp() - user_st.updated_at.timestamp(), 0);
     }
 
     #[tokio::test]
@@ -340,8 +489,8 @@ mod tests {
         // arrange
         let conn = conn().await;
         let mut user = User::from("arinono".to_string(), 42069);
-        user.follower_since = Some(Utc::now().to_string());
-        user.subscriber_since = Some(Utc::now().to_string());
+        user.follower_since = Some(Utc::now());
+        user.subscriber_since = Some(Utc::now());
         user.subgift_total = Some(123);
         user.subscription_tier = Some("Tier1".to_string());
 
@@ -364,6 +513,28 @@ mod tests {
         assert_eq!(user_st.subgift_total, Some(123));
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn create_with_created_at() {
+        // arrange
+        let conn = conn().await;
+        let user = User::builder("arinono".to_string(), 42069)
+            .created_at(Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap())
+            .build();
+
+        // act
+        let res = user.create(&conn).await;
+
+        // assert
+        assert!(res.is_ok());
+        let user_st = User::get(&conn, res.unwrap()).await.unwrap().unwrap();
+        assert_eq!(
+            user_st.created_at,
+            Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap()
+        );
+        assert_ne!(user_st.updated_at, user_st.created_at);
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn create_with_errors_subscription_since() {
@@ -372,7 +543,7 @@ mod tests {
         let mut user = User::from("arinono".to_string(), 42069);
 
         // act
-        user.subscriber_since = Some(Utc::now().to_string());
+        user.subscriber_since = Some(Utc::now());
         user.subscription_tier = None;
         let res = user.create(&conn).await;
 
@@ -412,7 +583,7 @@ mod tests {
         let mut user = User::from("arinono".to_string(), 42069);
 
         // act
-        user.subscriber_since = Some(Utc::now().to_string());
+        user.subscriber_since = Some(Utc::now());
         user.subscription_tier = Some("Invalid".to_string());
         let res = user.create(&conn).await;
 
@@ -504,13 +675,13 @@ mod tests {
         let user = User::from("arinono".to_string(), 42069);
         let id = user.create(&conn).await.unwrap();
         let mut user_st = User::get(&conn, id).await.unwrap().unwrap();
-        let created_at = user_st.created_at.clone();
-        let updated_at = user_st.updated_at.clone();
+        let created_at = user_st.created_at;
+        let updated_at = user_st.updated_at;
 
         // act
         user_st.display_name = "changed".to_string();
-        let now_utc_str = Utc::now().to_utc().to_string();
-        user_st.follower_since = Some(now_utc_str.clone());
+        let now = Utc::now().trunc_subsecs(3);
+        user_st.follower_since = Some(now);
         let res = user_st.update(&conn).await;
 
         // assert
@@ -519,7 +690,7 @@ mod tests {
         assert_eq!(created_at, user_st.created_at);
         assert_eq!(user.twitch_id, user_st.twitch_id);
         assert_ne!(updated_at, user_st.updated_at);
-        assert_eq!(user_st.follower_since, Some(now_utc_str));
+        assert_eq!(user_st.follower_since, Some(now));
     }
 
     #[tokio::test]
@@ -555,7 +726,7 @@ mod tests {
 
         // act
         user_st.subscription_tier = None;
-        user_st.subscriber_since = Some(String::new());
+        user_st.subscriber_since = Some(Utc::now());
         let res = user_st.update(&conn).await;
 
         // assert
@@ -623,4 +794,278 @@ mod tests {
         let user_st = user_st.clone().unwrap();
         assert!(user_st.is_none());
     }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn list() {
+        // arrange
+        let conn = conn().await;
+        for i in 0..3 {
+            let user = User::from(format!("arinono{}", i), 42069 + i);
+            user.create(&conn).await.unwrap();
+        }
+        let deleted = User::get(&conn, 1).await.unwrap().unwrap();
+        deleted.delete(&conn).await.unwrap();
+
+        // act
+        let first_page = User::list(&conn, 1, 0).await;
+        let second_page = User::list(&conn, 1, 1).await;
+        let third_page = User::list(&conn, 1, 2).await;
+
+        // assert
+        assert!(first_page.is_ok());
+        let first_page = first_page.unwrap();
+        assert_eq!(first_page.len(), 1);
+        assert_eq!(first_page[0].display_name, "arinono2".to_string());
+        assert_eq!(second_page.unwrap()[0].display_name, "arinono1".to_string());
+        assert!(third_page.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn flag_suspected() {
+        // arrange
+        let conn = conn().await;
+        let user = User::from("arinono".to_string(), 42069);
+        let id = user.create(&conn).await.unwrap();
+
+        // act
+        let res = User::flag_suspected(&conn, id).await;
+
+        // assert
+        assert!(res.is_ok());
+        let user_st = User::get(&conn, id).await.unwrap().unwrap();
+        assert!(user_st.suspected_at.is_some());
+
+        User::flag_suspected(&conn, id).await.unwrap();
+        let flagged_again = User::get(&conn, id).await.unwrap().unwrap();
+        assert_eq!(flagged_again.suspected_at, user_st.suspected_at);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn delete_suspected() {
+        // arrange
+        let conn = conn().await;
+        let mut ids = vec![];
+        for i in 0..4 {
+            let user = User::from(format!("arinono{}", i), 42069 + i);
+            let id = user.create(&conn).await.unwrap();
+            if i > 0 {
+                User::flag_suspected(&conn, id).await.unwrap();
+            }
+            ids.push(id);
+        }
+
+        // act
+        let some = User::delete_suspected(&conn, Some(&[ids[0], ids[1]])).await;
+        let rest = User::delete_suspected(&conn, None).await;
+
+        // assert
+        assert_eq!(some, Ok(1));
+        assert_eq!(rest, Ok(2));
+        assert!(User::get(&conn, ids[0]).await.unwrap().is_some());
+        for id in &ids[1..] {
+            assert!(User::get(&conn, *id).await.unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn list_suspected() {
+        // arrange
+        let conn = conn().await;
+        for i in 0..4 {
+            let user = User::from(format!("arinono{}", i), 42069 + i);
+            let id = user.create(&conn).await.unwrap();
+            if i > 0 {
+                User::flag_suspected(&conn, id).await.unwrap();
+            }
+        }
+        let deleted = User::get(&conn, 2).await.unwrap().unwrap();
+        deleted.delete(&conn).await.unwrap();
+
+        // act
+        let res = User::list_suspected(&conn, 10, 0).await;
+
+        // assert
+        assert!(res.is_ok());
+        let names: Vec<String> = res.unwrap().into_iter().map(|u| u.display_name).collect();
+        assert_eq!(names, vec!["arinono3".to_string(), "arinono2".to_string()]);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn set_profile() {
+        // arrange
+        let conn = conn().await;
+        let user = User::from("Arinono".to_string(), 42069);
+        let id = user.create(&conn).await.unwrap();
+        let profile = UserProfile {
+            login: "arinono".to_string(),
+            account_created_at: Utc.with_ymd_and_hms(2016, 12, 14, 20, 32, 28).unwrap(),
+            profile_image_url: None,
+            broadcaster_type: "affiliate".to_string(),
+        };
+
+        // act
+        let res = User::set_profile(&conn, id, &profile).await;
+
+        // assert
+        assert!(res.is_ok());
+        let user_st = User::get(&conn, id).await.unwrap().unwrap();
+        assert_eq!(user_st.login, Some("arinono".to_string()));
+        assert_eq!(user_st.account_created_at, Some(profile.account_created_at));
+        assert_eq!(user_st.profile_image_url, None);
+        assert_eq!(user_st.broadcaster_type, Some("affiliate".to_string()));
+        assert!(user_st.enriched_at.is_some());
+        assert_eq!(user_st.profile(), Some(profile));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn upsert_by_twitch_id_new_user() {
+        // arrange
+        let conn = conn().await;
+        let at = Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap();
+        let patch = UserPatch::follow(at);
+
+        // act
+        let res = User::upsert_by_twitch_id(&conn, 42069, "arinono", &patch, &at).await;
+
+        // assert
+        assert!(res.is_ok());
+        let user_st = User::get(&conn, res.unwrap()).await.unwrap().unwrap();
+        assert_eq!(user_st.display_name, "arinono".to_string());
+        assert_eq!(user_st.twitch_id, 42069);
+        assert_eq!(user_st.follower_since, Some(at));
+        assert_eq!(user_st.created_at, at);
+        assert!(user_st.subscriber_since.is_none());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn upsert_by_twitch_id_existing_user() {
+        // arrange
+        let conn = conn().await;
+        let followed_at = Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap();
+        let subscribed_at = Utc.with_ymd_and_hms(2025, 2, 11, 20, 0, 0).unwrap();
+        let id = User::builder("arinono".to_string(), 42069)
+            .follow(followed_at)
+            .build()
+            .create(&conn)
+            .await
+            .unwrap();
+        let patch = UserPatch::subscribe(subscribed_at, "Tier2".to_string());
+
+        // act
+        let res = User::upsert_by_twitch_id(&conn, 42069, "Arinono", &patch, &Utc::now()).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), id);
+        let user_st = User::get(&conn, id).await.unwrap().unwrap();
+        assert_eq!(user_st.display_name, "Arinono".to_string());
+        assert_eq!(user_st.follower_since, Some(followed_at));
+        assert_eq!(user_st.subscriber_since, Some(subscribed_at));
+        assert_eq!(user_st.subscription_tier, Some("Tier2".to_string()));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn upsert_by_twitch_id_resubscribe() {
+        // arrange
+        let conn = conn().await;
+        let subscribed_at = Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap();
+        let resubscribed_at = Utc.with_ymd_and_hms(2025, 3, 10, 20, 0, 0).unwrap();
+        let id = User::builder("arinono".to_string(), 42069)
+            .subscribe(subscribed_at)
+            .build()
+            .create(&conn)
+            .await
+            .unwrap();
+        let patch = UserPatch::resubscribe(resubscribed_at, "Tier3".to_string());
+
+        // act
+        let existing =
+            User::upsert_by_twitch_id(&conn, 42069, "arinono", &patch, &resubscribed_at).await;
+        let new = User::upsert_by_twitch_id(&conn, 42070, "jdoe", &patch, &resubscribed_at).await;
+
+        // assert
+        let user_st = User::get(&conn, existing.unwrap()).await.unwrap().unwrap();
+        assert_eq!(user_st.id, id);
+        assert_eq!(user_st.subscriber_since, Some(subscribed_at));
+        assert_eq!(user_st.subscription_tier, Some("Tier3".to_string()));
+        let user_st = User::get(&conn, new.unwrap()).await.unwrap().unwrap();
+        assert_eq!(user_st.subscriber_since, Some(resubscribed_at));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn upsert_by_twitch_id_unsubscribe() {
+        // arrange
+        let conn = conn().await;
+        let id = User::builder("arinono".to_string(), 42069)
+            .subscribe(Utc.with_ymd_and_hms(2025, 2, 10, 20, 0, 0).unwrap())
+            .build()
+            .create(&conn)
+            .await
+            .unwrap();
+
+        // act
+        let res = User::upsert_by_twitch_id(
+            &conn,
+            42069,
+            "arinono",
+            &UserPatch::unsubscribe(),
+            &Utc::now(),
+        )
+        .await;
+
+        // assert
+        assert!(res.is_ok());
+        let user_st = User::get(&conn, id).await.unwrap().unwrap();
+        assert!(user_st.subscriber_since.is_none());
+        assert!(user_st.subscription_tier.is_none());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn upsert_by_twitch_id_with_errors() {
+        // arrange
+        let conn = conn().await;
+        let user = User::from("arinono".to_string(), 42069);
+        let id = user.create(&conn).await.unwrap();
+        User::get(&conn, id)
+            .await
+            .unwrap()
+            .unwrap()
+            .delete(&conn)
+            .await
+            .unwrap();
+        let at = Utc::now();
+        let bad_tier = UserPatch::subscribe(at, "Tier4".to_string());
+        let half_sub = UserPatch {
+            subscription_tier: Some(Some("Tier1".to_string())),
+            ..UserPatch::default()
+        };
+
+        // act
+        let deleted =
+            User::upsert_by_twitch_id(&conn, 42069, "arinono", &UserPatch::default(), &at).await;
+        let bad_tier = User::upsert_by_twitch_id(&conn, 1, "arinono", &bad_tier, &at).await;
+        let half_sub = User::upsert_by_twitch_id(&conn, 1, "arinono", &half_sub, &at).await;
+
+        // assert
+        assert_eq!(
+            deleted.unwrap_err(),
+            OrmError::NoChange("User 42069 is deleted".to_string())
+        );
+        assert_eq!(
+            bad_tier.unwrap_err(),
+            OrmError::BadInput("Invalid sub tier name".to_string())
+        );
+        assert!(half_sub.is_err());
+        assert!(User::get_by_twitch_id(&conn, 1).await.unwrap().is_none());
+    }
 }
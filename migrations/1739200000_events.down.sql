@@ -0,0 +1,3 @@
+-- Write your down sql migration here
+drop index if exists events_message_id_idx;
+drop table if exists events;
@@ -0,0 +1,16 @@
+-- Write your up sql migration here
+create table if not exists events (
+  id integer primary key autoincrement,
+  message_id text,
+  subscription_type text,
+  subscription_version text,
+  message_timestamp text,
+  raw text,
+  user_id integer,
+  kind text,
+  payload text,
+  created_at text not null,
+  foreign key (user_id) references users (id) on delete set null
+);
+
+create unique index if not exists events_message_id_idx on events(message_id);
@@ -0,0 +1,4 @@
+-- Write your down sql migration here
+drop trigger if exists insert_raid;
+alter table latests drop column raid;
+drop table if exists raids;
@@ -0,0 +1,24 @@
+-- Write your up sql migration here
+create table raids (
+  id integer primary key,
+  user_id integer,
+  viewers integer not null,
+  created_at text not null,
+  foreign key (user_id) references users (id) on delete cascade
+);
+
+alter table latests add column raid integer default null references raids(id);
+
+create trigger if not exists insert_raid
+  after insert on raids
+  begin
+    insert into latests (id, raid)
+      values (1, (
+        select r.id from raids r
+          inner join users u on u.id = r.user_id
+          where u.deleted_at is null
+        order by r.created_at desc, r.id desc
+        limit 1
+      )) on conflict (id)
+      do update set raid = excluded.raid;
+end;
@@ -0,0 +1,4 @@
+-- Write your down sql migration here
+drop trigger if exists insert_resub;
+alter table latests drop column resub;
+drop table if exists resubs;
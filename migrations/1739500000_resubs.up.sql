@@ -0,0 +1,28 @@
+-- Write your up sql migration here
+create table resubs (
+  id integer primary key,
+  user_id integer,
+  tier text not null,
+  cumulative_months integer not null,
+  streak_months integer,
+  duration_months integer not null,
+  message text,
+  created_at text not null,
+  foreign key (user_id) references users (id) on delete cascade
+);
+
+alter table latests add column resub integer default null references resubs(id);
+
+create trigger if not exists insert_resub
+  after insert on resubs
+  begin
+    insert into latests (id, resub)
+      values (1, (
+        select r.id from resubs r
+          inner join users u on u.id = r.user_id
+          where u.deleted_at is null
+        order by r.created_at desc, r.id desc
+        limit 1
+      )) on conflict (id)
+      do update set resub = excluded.resub;
+end;
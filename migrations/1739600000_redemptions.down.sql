@@ -0,0 +1,4 @@
+-- Write your down sql migration here
+drop index if exists redemptions_reward_id_idx;
+drop index if exists redemptions_redemption_id_idx;
+drop table if exists redemptions;
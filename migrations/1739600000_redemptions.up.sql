@@ -0,0 +1,17 @@
+-- Write your up sql migration here
+create table redemptions (
+  id integer primary key,
+  redemption_id text not null,
+  user_id integer,
+  reward_id text not null,
+  title text not null,
+  cost integer not null,
+  user_input text,
+  status text not null,
+  created_at text not null,
+  updated_at text not null,
+  foreign key (user_id) references users (id) on delete cascade
+);
+
+create unique index redemptions_redemption_id_idx on redemptions(redemption_id);
+create index redemptions_reward_id_idx on redemptions(reward_id);
@@ -0,0 +1,9 @@
+-- Write your down sql migration here
+alter table redemptions drop column stream_id;
+alter table raids drop column stream_id;
+alter table resubs drop column stream_id;
+alter table subgifts drop column stream_id;
+alter table bits drop column stream_id;
+alter table events drop column stream_id;
+drop index if exists streams_stream_id_idx;
+drop table if exists streams;
@@ -0,0 +1,18 @@
+-- Write your up sql migration here
+create table streams (
+  id integer primary key,
+  stream_id text not null,
+  stream_type text not null,
+  started_at text not null,
+  ended_at text,
+  created_at text not null
+);
+
+create unique index streams_stream_id_idx on streams(stream_id);
+
+alter table events add column stream_id integer default null references streams(id) on delete set null;
+alter table bits add column stream_id integer default null references streams(id) on delete set null;
+alter table subgifts add column stream_id integer default null references streams(id) on delete set null;
+alter table resubs add column stream_id integer default null references streams(id) on delete set null;
+alter table raids add column stream_id integer default null references streams(id) on delete set null;
+alter table redemptions add column stream_id integer default null references streams(id) on delete set null;
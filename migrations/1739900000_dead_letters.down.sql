@@ -0,0 +1,3 @@
+-- Write your down sql migration here
+drop index if exists dead_letters_sink_idx;
+drop table if exists dead_letters;
@@ -0,0 +1,12 @@
+-- Write your up sql migration here
+create table dead_letters (
+  id integer primary key,
+  sink text not null,
+  target text not null,
+  payload text not null,
+  attempts integer not null,
+  error text,
+  created_at text not null
+);
+
+create index dead_letters_sink_idx on dead_letters(sink);
@@ -0,0 +1,3 @@
+-- Write your down sql migration here
+drop index if exists outbox_available_at_idx;
+drop table if exists outbox;
@@ -0,0 +1,13 @@
+-- Write your up sql migration here
+create table outbox (
+  id integer primary key,
+  sink text not null,
+  target text not null,
+  payload text not null,
+  attempts integer not null default 0,
+  error text,
+  available_at text not null,
+  created_at text not null
+);
+
+create index outbox_available_at_idx on outbox(available_at);
@@ -0,0 +1,3 @@
+-- Write your down sql migration here
+drop index if exists users_suspected_at_idx;
+alter table users drop column suspected_at;
@@ -0,0 +1,4 @@
+-- Write your up sql migration here
+alter table users add column suspected_at text default null;
+
+create index users_suspected_at_idx on users(suspected_at);
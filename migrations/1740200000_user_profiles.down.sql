@@ -0,0 +1,6 @@
+-- Write your down sql migration here
+alter table users drop column enriched_at;
+alter table users drop column broadcaster_type;
+alter table users drop column profile_image_url;
+alter table users drop column account_created_at;
+alter table users drop column login;
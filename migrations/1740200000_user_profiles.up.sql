@@ -0,0 +1,6 @@
+-- Write your up sql migration here
+alter table users add column login text default null;
+alter table users add column account_created_at text default null;
+alter table users add column profile_image_url text default null;
+alter table users add column broadcaster_type text default null;
+alter table users add column enriched_at text default null;
@@ -0,0 +1,2 @@
+-- Write your down sql migration here
+-- the original formats are not kept, normalized timestamps stay as they are
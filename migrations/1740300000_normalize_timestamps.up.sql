@@ -0,0 +1,63 @@
+-- Write your up sql migration here
+-- store every timestamp as strftime('%Y-%m-%dT%H:%M:%fZ'), which sorts as text
+update users set
+  follower_since = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(follower_since, ' UTC', 'Z')), follower_since),
+  subscriber_since = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(subscriber_since, ' UTC', 'Z')), subscriber_since),
+  created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(created_at, ' UTC', 'Z')), created_at),
+  updated_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(updated_at, ' UTC', 'Z')), updated_at),
+  deleted_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(deleted_at, ' UTC', 'Z')), deleted_at),
+  suspected_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(suspected_at, ' UTC', 'Z')), suspected_at),
+  account_created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(account_created_at, ' UTC', 'Z')), account_created_at),
+  enriched_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(enriched_at, ' UTC', 'Z')), enriched_at);
+update subgifts set
+  created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(created_at, ' UTC', 'Z')), created_at);
+update bits set
+  created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(created_at, ' UTC', 'Z')), created_at);
+update resubs set
+  created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(created_at, ' UTC', 'Z')), created_at);
+update raids set
+  created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(created_at, ' UTC', 'Z')), created_at);
+update redemptions set
+  created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(created_at, ' UTC', 'Z')), created_at),
+  updated_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(updated_at, ' UTC', 'Z')), updated_at);
+update streams set
+  started_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(started_at, ' UTC', 'Z')), started_at),
+  ended_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(ended_at, ' UTC', 'Z')), ended_at),
+  created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(created_at, ' UTC', 'Z')), created_at);
+update events set
+  created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(created_at, ' UTC', 'Z')), created_at);
+update outbox set
+  available_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(available_at, ' UTC', 'Z')), available_at),
+  created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(created_at, ' UTC', 'Z')), created_at);
+update dead_letters set
+  created_at = coalesce(strftime('%Y-%m-%dT%H:%M:%fZ', replace(created_at, ' UTC', 'Z')), created_at);
+update latests set
+  subgift = (
+    select s.id from subgifts s
+      inner join users u on u.id = s.user_id
+      where u.deleted_at is null
+    order by s.created_at desc
+    limit 1
+  ),
+  bit = (
+    select b.id from bits b
+      inner join users u on u.id = b.user_id
+      where u.deleted_at is null
+    order by b.created_at desc
+    limit 1
+  ),
+  raid = (
+    select r.id from raids r
+      inner join users u on u.id = r.user_id
+      where u.deleted_at is null
+    order by r.created_at desc, r.id desc
+    limit 1
+  ),
+  resub = (
+    select r.id from resubs r
+      inner join users u on u.id = r.user_id
+      where u.deleted_at is null
+    order by r.created_at desc, r.id desc
+    limit 1
+  )
+where id = 1;
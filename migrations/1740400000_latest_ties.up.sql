@@ -0,0 +1,34 @@
+-- Write your up sql migration here
+-- rows created in the same millisecond are told apart by id, like raids and resubs
+drop trigger insert_subgift;
+create trigger insert_subgift
+  after insert on subgifts
+  begin
+    insert into latests (id, subgift)
+      values (1, (
+        select s.id from subgifts s
+          inner join users u on u.id = s.user_id
+          where u.deleted_at is null
+        order by s.created_at desc, s.id desc
+        limit 1
+      )) on conflict (id)
+      do update set subgift = excluded.subgift;
+
+    update users
+      set subgift_total = (select sum(number) from subgifts where user_id = new.user_id)
+    where id = new.user_id;
+end;
+drop trigger insert_bit;
+create trigger insert_bit
+  after insert on bits
+  begin
+    insert into latests (id, bit)
+      values (1, (
+        select b.id from bits b
+          inner join users u on u.id = b.user_id
+          where u.deleted_at is null
+        order by b.created_at desc, b.id desc
+        limit 1
+      )) on conflict (id)
+      do update set bit = excluded.bit;
+end;
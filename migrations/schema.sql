@@ -15,14 +15,14 @@ CREATE TABLE users (
   created_at text not null,
   updated_at text not null,
   deleted_at text
-);
+, suspected_at text default null, login text default null, account_created_at text default null, profile_image_url text default null, broadcaster_type text default null, enriched_at text default null);
 CREATE UNIQUE INDEX users_twitch_id_idx on users(twitch_id);
 CREATE TABLE latests (
   id integer primary key,
   follower integer default null,
   subscriber integer default null,
   subgift integer default null,
-  bit integer default null,
+  bit integer default null, raid integer default null references raids(id), resub integer default null references resubs(id),
   foreign key (follower) references users(id),
   foreign key (subscriber) references users(id),
   foreign key (subgift) references subgifts(id),
@@ -85,7 +85,7 @@ CREATE TABLE "subgifts" (
   user_id integer,
   number integer not null,
   tier text not null,
-  created_at text not null,
+  created_at text not null, stream_id integer default null references streams(id) on delete set null,
   foreign key (user_id) references users (id) on delete cascade
 );
 CREATE TABLE "bits" (
@@ -93,9 +93,113 @@ CREATE TABLE "bits" (
   user_id integer,
   number integer not null,
   message text,
+  created_at text not null, stream_id integer default null references streams(id) on delete set null,
+  foreign key (user_id) references users (id) on delete cascade
+);
+CREATE TABLE events (
+  id integer primary key autoincrement,
+  message_id text,
+  subscription_type text,
+  subscription_version text,
+  message_timestamp text,
+  raw text,
+  user_id integer,
+  kind text,
+  payload text,
+  created_at text not null, stream_id integer default null references streams(id) on delete set null,
+  foreign key (user_id) references users (id) on delete set null
+);
+CREATE UNIQUE INDEX events_message_id_idx on events(message_id);
+CREATE TABLE raids (
+  id integer primary key,
+  user_id integer,
+  viewers integer not null,
+  created_at text not null, stream_id integer default null references streams(id) on delete set null,
+  foreign key (user_id) references users (id) on delete cascade
+);
+CREATE TRIGGER insert_raid
+  after insert on raids
+  begin
+    insert into latests (id, raid)
+      values (1, (
+        select r.id from raids r
+          inner join users u on u.id = r.user_id
+          where u.deleted_at is null
+        order by r.created_at desc, r.id desc
+        limit 1
+      )) on conflict (id)
+      do update set raid = excluded.raid;
+end;
+CREATE TABLE resubs (
+  id integer primary key,
+  user_id integer,
+  tier text not null,
+  cumulative_months integer not null,
+  streak_months integer,
+  duration_months integer not null,
+  message text,
+  created_at text not null, stream_id integer default null references streams(id) on delete set null,
+  foreign key (user_id) references users (id) on delete cascade
+);
+CREATE TRIGGER insert_resub
+  after insert on resubs
+  begin
+    insert into latests (id, resub)
+      values (1, (
+        select r.id from resubs r
+          inner join users u on u.id = r.user_id
+          where u.deleted_at is null
+        order by r.created_at desc, r.id desc
+        limit 1
+      )) on conflict (id)
+      do update set resub = excluded.resub;
+end;
+CREATE TABLE redemptions (
+  id integer primary key,
+  redemption_id text not null,
+  user_id integer,
+  reward_id text not null,
+  title text not null,
+  cost integer not null,
+  user_input text,
+  status text not null,
   created_at text not null,
+  updated_at text not null, stream_id integer default null references streams(id) on delete set null,
   foreign key (user_id) references users (id) on delete cascade
 );
+CREATE UNIQUE INDEX redemptions_redemption_id_idx on redemptions(redemption_id);
+CREATE INDEX redemptions_reward_id_idx on redemptions(reward_id);
+CREATE TABLE streams (
+  id integer primary key,
+  stream_id text not null,
+  stream_type text not null,
+  started_at text not null,
+  ended_at text,
+  created_at text not null
+);
+CREATE UNIQUE INDEX streams_stream_id_idx on streams(stream_id);
+CREATE TABLE dead_letters (
+  id integer primary key,
+  sink text not null,
+  target text not null,
+  payload text not null,
+  attempts integer not null,
+  error text,
+  created_at text not null
+);
+CREATE INDEX dead_letters_sink_idx on dead_letters(sink);
+CREATE TABLE outbox (
+  id integer primary key,
+  sink text not null,
+  target text not null,
+  payload text not null,
+  attempts integer not null default 0,
+  error text,
+  available_at text not null,
+  created_at text not null
+);
+CREATE INDEX outbox_available_at_idx on outbox(available_at);
+CREATE INDEX users_suspected_at_idx on users(suspected_at);
 CREATE TRIGGER insert_subgift
   after insert on subgifts
   begin
@@ -104,7 +208,7 @@ CREATE TRIGGER insert_subgift
         select s.id from subgifts s
           inner join users u on u.id = s.user_id
           where u.deleted_at is null
-        order by s.created_at desc
+        order by s.created_at desc, s.id desc
         limit 1
       )) on conflict (id)
       do update set subgift = excluded.subgift;
@@ -121,7 +225,7 @@ CREATE TRIGGER insert_bit
         select b.id from bits b
           inner join users u on u.id = b.user_id
           where u.deleted_at is null
-        order by b.created_at desc
+        order by b.created_at desc, b.id desc
         limit 1
       )) on conflict (id)
       do update set bit = excluded.bit;
@@ -1,69 +1,439 @@
-use axum::{extract::State, response::IntoResponse};
-use http::StatusCode;
-use tables::latests::Latests;
+use std::{convert::Infallible, time::Duration};
 
-use crate::AppState;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    routing::get,
+    Json,
+};
+use futures::Stream;
+use subtle::ConstantTimeEq;
+use tables::{
+    bits::Bit,
+    latests::{
+        LatestBit, LatestFollower, LatestRaid, LatestResub, LatestSubgift, LatestSubscriber,
+        Latests,
+    },
+    raids::Raid,
+    redemptions::{Redemption, RewardLeaderboardEntry},
+    resubs::Resub,
+    subgifts::Subgift,
+    user::User,
+};
+
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{AppState, Error};
+
+const DEFAULT_PER_PAGE: u64 = 50;
+const MAX_PER_PAGE: u64 = 100;
+const REPLAY_BATCH_SIZE: u64 = 100;
 
 pub fn routes() -> axum::Router<AppState> {
     axum::Router::new()
-        .route("/latest/follower", axum::routing::get(latest_follow))
-        .route("/latest/subscriber", axum::routing::get(latest_subscriber))
-        .route("/latest/subgift", axum::routing::get(latest_subgift))
-        .route("/latest/bits", axum::routing::get(latest_bits))
+        .route("/latest/follower", get(latest_follow))
+        .route("/latest/subscriber", get(latest_subscriber))
+        .route("/latest/resub", get(latest_resub))
+        .route("/latest/subgift", get(latest_subgift))
+        .route("/latest/bits", get(latest_bits))
+        .route("/latest/raid", get(latest_raid))
+        .route("/users", get(users))
+        .route(
+            "/users/suspected",
+            get(suspected_users).delete(delete_suspected_users),
+        )
+        .route("/bits", get(bits))
+        .route("/resubs", get(resubs))
+        .route("/subgifts", get(subgifts))
+        .route("/raids", get(raids))
+        .route("/rewards/:reward_id/leaderboard", get(reward_leaderboard))
+        .route("/streams", get(streams))
+        .route("/events/stream", get(events_stream))
+        .route("/events/ws", get(events_ws))
 }
 
-async fn latest_follow(State(state): State<AppState>) -> impl IntoResponse {
-    let db = state.database.clone();
-    let conn = db.conn().unwrap();
+#[derive(Debug, serde::Deserialize)]
+pub struct Pagination {
+    page: Option<u64>,
+    per_page: Option<u64>,
+}
+
+impl Pagination {
+    fn page(&self) -> u64 {
+        self.page.unwrap_or(1).max(1)
+    }
 
-    let follower = Latests::get_latest_follower(&conn)
-        .await
-        .expect("Failed to get latest follower");
+    fn per_page(&self) -> u64 {
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE)
+    }
 
-    match follower {
-        Some(follow) => (StatusCode::OK, follow.name),
-        None => (StatusCode::NOT_FOUND, "No follow found".to_owned()),
+    /// Pages past what SQLite can offset by are refused rather than wrapped.
+    fn offset(&self) -> Result<u64, Error> {
+        (self.page() - 1)
+            .checked_mul(self.per_page())
+            .filter(|offset| *offset <= i64::MAX as u64)
+            .ok_or(Error::BadRequest(format!("Invalid page {}", self.page())))
     }
 }
 
-async fn latest_subscriber(State(state): State<AppState>) -> impl IntoResponse {
-    let db = state.database.clone();
-    let conn = db.conn().unwrap();
+#[derive(Debug, serde::Serialize)]
+pub struct Page<T> {
+    data: Vec<T>,
+    page: u64,
+    per_page: u64,
+}
+
+impl<T> Page<T> {
+    fn from(data: Vec<T>, pagination: &Pagination) -> Self {
+        Self {
+            data,
+            page: pagination.page(),
+            per_page: pagination.per_page(),
+        }
+    }
+}
 
-    let subscriber = Latests::get_latest_subscriber(&conn)
-        .await
-        .expect("Failed to get latest subscriber");
+#[derive(Debug, serde::Deserialize)]
+pub struct Cursor {
+    after: Option<u64>,
+}
 
-    match subscriber {
-        Some(sub) => (StatusCode::OK, sub.name),
-        None => (StatusCode::NOT_FOUND, "No subscriber found".to_owned()),
+async fn latest_follow(State(state): State<AppState>) -> Result<Json<LatestFollower>, Error> {
+    let conn = state.database.conn()?;
+
+    Latests::get_latest_follower(&conn)
+        .await?
+        .map(Json)
+        .ok_or(Error::NotFound("No follower found".to_string()))
+}
+
+async fn latest_subscriber(State(state): State<AppState>) -> Result<Json<LatestSubscriber>, Error> {
+    let conn = state.database.conn()?;
+
+    Latests::get_latest_subscriber(&conn)
+        .await?
+        .map(Json)
+        .ok_or(Error::NotFound("No subscriber found".to_string()))
+}
+
+async fn latest_resub(State(state): State<AppState>) -> Result<Json<LatestResub>, Error> {
+    let conn = state.database.conn()?;
+
+    Latests::get_latest_resub(&conn)
+        .await?
+        .map(Json)
+        .ok_or(Error::NotFound("No resub found".to_string()))
+}
+
+async fn latest_subgift(State(state): State<AppState>) -> Result<Json<LatestSubgift>, Error> {
+    let conn = state.database.conn()?;
+
+    Latests::get_latest_subgift(&conn)
+        .await?
+        .map(Json)
+        .ok_or(Error::NotFound("No subgift found".to_string()))
+}
+
+async fn latest_bits(State(state): State<AppState>) -> Result<Json<LatestBit>, Error> {
+    let conn = state.database.conn()?;
+
+    Latests::get_latest_bit(&conn)
+        .await?
+        .map(Json)
+        .ok_or(Error::NotFound("No bits found".to_string()))
+}
+
+async fn latest_raid(State(state): State<AppState>) -> Result<Json<LatestRaid>, Error> {
+    let conn = state.database.conn()?;
+
+    Latests::get_latest_raid(&conn)
+        .await?
+        .map(Json)
+        .ok_or(Error::NotFound("No raid found".to_string()))
+}
+
+async fn users(
+    State(state): State<AppState>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Page<User>>, Error> {
+    let conn = state.database.conn()?;
+
+    let users = User::list(&conn, pagination.per_page(), pagination.offset()?).await?;
+
+    Ok(Json(Page::from(users, &pagination)))
+}
+
+async fn suspected_users(
+    State(state): State<AppState>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Page<User>>, Error> {
+    let conn = state.database.conn()?;
+
+    let users = User::list_suspected(&conn, pagination.per_page(), pagination.offset()?).await?;
+
+    Ok(Json(Page::from(users, &pagination)))
+}
+
+/// Restricts a route to callers sending `Authorization: Bearer <NOST_API_TOKEN>`,
+/// and to nobody when the token is not set. The token is compared in constant
+/// time so its prefix can't be guessed from response times.
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(), Error> {
+    let Some(token) = &state.env.api_token else {
+        return Err(Error::Unauthorized("NOST_API_TOKEN is not set".to_string()));
+    };
+
+    let bearer = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    match bearer {
+        Some(bearer) if bool::from(bearer.as_bytes().ct_eq(token.secret())) => Ok(()),
+        _ => Err(Error::Unauthorized("Invalid API token".to_string())),
     }
 }
 
-async fn latest_subgift(State(state): State<AppState>) -> impl IntoResponse {
-    let db = state.database.clone();
-    let conn = db.conn().unwrap();
+#[derive(Debug, serde::Deserialize)]
+pub struct DeleteSuspected {
+    ids: Option<Vec<u64>>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct Deleted {
+    deleted: u64,
+}
+
+/// Soft-deletes the suspected users listed in `ids`, or all of them when
+/// `ids` is left out. Ids of users that are not flagged are skipped.
+async fn delete_suspected_users(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<DeleteSuspected>,
+) -> Result<Json<Deleted>, Error> {
+    authorize(&state, &headers)?;
+    let conn = state.database.conn()?;
+
+    let deleted = User::delete_suspected(&conn, body.ids.as_deref()).await?;
 
-    let subgift = Latests::get_latest_subgift(&conn)
-        .await
-        .expect("Failed to get latest subgift");
+    tracing::info!("Deleted {} suspected users", deleted);
 
-    match subgift {
-        Some(subgift) => (StatusCode::OK, subgift.name),
-        None => (StatusCode::NOT_FOUND, "No subgift found".to_owned()),
+    Ok(Json(Deleted { deleted }))
+}
+
+async fn bits(
+    State(state): State<AppState>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Page<Bit>>, Error> {
+    let conn = state.database.conn()?;
+
+    let bits = Bit::list(&conn, pagination.per_page(), pagination.offset()?).await?;
+
+    Ok(Json(Page::from(bits, &pagination)))
+}
+
+async fn resubs(
+    State(state): State<AppState>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Page<Resub>>, Error> {
+    let conn = state.database.conn()?;
+
+    let resubs = Resub::list(&conn, pagination.per_page(), pagination.offset()?).await?;
+
+    Ok(Json(Page::from(resubs, &pagination)))
+}
+
+async fn subgifts(
+    State(state): State<AppState>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Page<Subgift>>, Error> {
+    let conn = state.database.conn()?;
+
+    let subgifts = Subgift::list(&conn, pagination.per_page(), pagination.offset()?).await?;
+
+    Ok(Json(Page::from(subgifts, &pagination)))
+}
+
+async fn raids(
+    State(state): State<AppState>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Page<Raid>>, Error> {
+    let conn = state.database.conn()?;
+
+    let raids = Raid::list(&conn, pagination.per_page(), pagination.offset()?).await?;
+
+    Ok(Json(Page::from(raids, &pagination)))
+}
+
+async fn reward_leaderboard(
+    State(state): State<AppState>,
+    Path(reward_id): Path<String>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Page<RewardLeaderboardEntry>>, Error> {
+    let conn = state.database.conn()?;
+
+    let entries = Redemption::leaderboard(
+        &conn,
+        &reward_id,
+        pagination.per_page(),
+        pagination.offset()?,
+    )
+    .await?;
+
+    Ok(Json(Page::from(entries, &pagination)))
+}
+
+async fn streams(
+    State(state): State<AppState>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Page<tables::streams::Stream>>, Error> {
+    let conn = state.database.conn()?;
+
+    let streams =
+        tables::streams::Stream::list(&conn, pagination.per_page(), pagination.offset()?).await?;
+
+    Ok(Json(Page::from(streams, &pagination)))
+}
+
+async fn events_stream(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.bus.subscribe();
+
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let sse_event = Event::default()
+                        .event(event.name())
+                        .json_data(&event)
+                        .unwrap_or_else(|e| Event::default().comment(e.to_string()));
+                    return Some((Ok(sse_event), receiver));
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped = skipped, "event stream client lagging behind");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::new().interval(Duration::from_secs(15)))
+}
+
+async fn events_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(cursor): Query<Cursor>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| events_socket(socket, state, cursor.after))
+}
+
+/// Sends every persisted event after the cursor, then switches to live
+/// delivery. Returns the id of the last event sent.
+async fn replay(state: &AppState, socket: &mut WebSocket, after: u64) -> eyre::Result<u64> {
+    let conn = state.database.conn()?;
+    let mut cursor = after;
+
+    loop {
+        let events = tables::events::Event::list_after(&conn, cursor, REPLAY_BATCH_SIZE).await?;
+
+        for event in events.iter() {
+            let text = crate::bus::stored_event_json(event)?;
+            socket.send(Message::Text(text)).await?;
+            cursor = event.id;
+        }
+
+        if (events.len() as u64) < REPLAY_BATCH_SIZE {
+            return Ok(cursor);
+        }
     }
 }
 
-async fn latest_bits(State(state): State<AppState>) -> impl IntoResponse {
-    let db = state.database.clone();
-    let conn = db.conn().unwrap();
+async fn events_socket(mut socket: WebSocket, state: AppState, after: Option<u64>) {
+    // subscribe before replaying so nothing falls between the two
+    let mut receiver = state.bus.subscribe();
+
+    let last_replayed = match after {
+        None => 0,
+        Some(after) => match replay(&state, &mut socket, after).await {
+            Ok(last) => last,
+            Err(e) => {
+                tracing::error!("Failed to replay events: {:#}", e);
+                return;
+            }
+        },
+    };
+
+    loop {
+        tokio::select! {
+            event = receiver.recv() => match event {
+                Ok(event) => {
+                    if event.id.is_some_and(|id| id <= last_replayed) {
+                        continue;
+                    }
+
+                    let text = match serde_json::to_string(&event) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            tracing::error!("Failed to serialize event: {:#}", e);
+                            continue;
+                        }
+                    };
+
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    // the client reconnects with its cursor and gets a replay
+                    tracing::warn!(skipped = skipped, "event socket client lagging behind");
+                    let _ = socket.send(Message::Close(None)).await;
+                    return;
+                }
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    #[test]
+    #[traced_test]
+    fn pagination_offset() {
+        // arrange
+        let pagination = |page| Pagination {
+            page: Some(page),
+            per_page: Some(MAX_PER_PAGE),
+        };
 
-    let bits = Latests::get_latest_bit(&conn)
-        .await
-        .expect("Failed to get latest bit");
+        // act
+        let first = pagination(1).offset();
+        let third = pagination(3).offset();
+        let overflow = pagination(u64::MAX).offset();
+        let too_far = pagination(i64::MAX as u64 / MAX_PER_PAGE + 2).offset();
 
-    match bits {
-        Some(bits) => (StatusCode::OK, bits.name),
-        None => (StatusCode::NOT_FOUND, "No subgift found".to_owned()),
+        // assert
+        assert_eq!(first.unwrap(), 0);
+        assert_eq!(third.unwrap(), 2 * MAX_PER_PAGE);
+        assert!(matches!(overflow, Err(Error::BadRequest(_))));
+        assert!(matches!(too_far, Err(Error::BadRequest(_))));
     }
 }
@@ -0,0 +1,317 @@
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+use twitch_api::eventsub::{
+    channel::{
+        ChannelCheerV1Payload, ChannelFollowV2Payload,
+        ChannelPointsCustomRewardRedemptionAddV1Payload, ChannelRaidV1Payload,
+        ChannelSubscribeV1Payload, ChannelSubscriptionEndV1Payload,
+        ChannelSubscriptionGiftV1Payload, ChannelSubscriptionMessageV1Payload,
+    },
+    stream::StreamOnlineV1Payload,
+    Event, Message, Payload,
+};
+
+use crate::{models::sub_tier::SubTier, twitch::projection::username};
+
+const CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventKind {
+    Follow {
+        user_id: String,
+        user_name: String,
+    },
+    Subscribe {
+        user_id: String,
+        user_name: String,
+        tier: SubTier,
+        is_gift: bool,
+    },
+    SubscribeEnd {
+        user_id: String,
+        user_name: String,
+        tier: SubTier,
+        is_gift: bool,
+    },
+    Resubscribe {
+        user_id: String,
+        user_name: String,
+        tier: SubTier,
+        cumulative_months: usize,
+        streak_months: Option<usize>,
+        duration_months: usize,
+        message: String,
+    },
+    Subgift {
+        user_id: Option<String>,
+        user_name: String,
+        tier: SubTier,
+        total: usize,
+        cumulative_total: Option<usize>,
+        is_anonymous: bool,
+    },
+    Cheer {
+        user_id: Option<String>,
+        user_name: String,
+        bits: usize,
+        message: String,
+        is_anonymous: bool,
+    },
+    Redemption {
+        user_id: String,
+        user_name: String,
+        reward_id: String,
+        title: String,
+        cost: usize,
+        user_input: String,
+    },
+    Raid {
+        user_id: String,
+        user_name: String,
+        viewers: usize,
+    },
+    StreamOnline {
+        stream_id: String,
+        started_at: String,
+    },
+    StreamOffline,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LiveEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+impl LiveEvent {
+    pub fn now(kind: EventKind) -> Self {
+        Self {
+            id: None,
+            at: Utc::now(),
+            kind,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.kind.name()
+    }
+}
+
+impl EventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::Follow { .. } => "follow",
+            EventKind::Subscribe { .. } => "subscribe",
+            EventKind::SubscribeEnd { .. } => "subscribe_end",
+            EventKind::Resubscribe { .. } => "resubscribe",
+            EventKind::Subgift { .. } => "subgift",
+            EventKind::Cheer { .. } => "cheer",
+            EventKind::Redemption { .. } => "redemption",
+            EventKind::Raid { .. } => "raid",
+            EventKind::StreamOnline { .. } => "stream_online",
+            EventKind::StreamOffline => "stream_offline",
+        }
+    }
+
+    /// Normalizes the notifications overlays care about, `None` for anything else.
+    pub fn from_event(event: &Event) -> Option<Self> {
+        use Message as M;
+        use Payload as P;
+
+        let kind = match event {
+            Event::ChannelFollowV2(P {
+                message:
+                    M::Notification(ChannelFollowV2Payload {
+                        user_id, user_name, ..
+                    }),
+                ..
+            }) => EventKind::Follow {
+                user_id: user_id.to_string(),
+                user_name: user_name.to_string(),
+            },
+            Event::ChannelSubscribeV1(P {
+                message:
+                    M::Notification(ChannelSubscribeV1Payload {
+                        user_id,
+                        user_name,
+                        tier,
+                        is_gift,
+                        ..
+                    }),
+                ..
+            }) => EventKind::Subscribe {
+                user_id: user_id.to_string(),
+                user_name: user_name.to_string(),
+                tier: SubTier::from(tier.clone()),
+                is_gift: *is_gift,
+            },
+            Event::ChannelSubscriptionEndV1(P {
+                message:
+                    M::Notification(ChannelSubscriptionEndV1Payload {
+                        user_id,
+                        user_name,
+                        tier,
+                        is_gift,
+                        ..
+                    }),
+                ..
+            }) => EventKind::SubscribeEnd {
+                user_id: user_id.to_string(),
+                user_name: user_name.to_string(),
+                tier: SubTier::from(tier.clone()),
+                is_gift: *is_gift,
+            },
+            Event::ChannelSubscriptionMessageV1(P {
+                message:
+                    M::Notification(ChannelSubscriptionMessageV1Payload {
+                        user_id,
+                        user_name,
+                        tier,
+                        cumulative_months,
+                        streak_months,
+                        duration_months,
+                        message,
+                        ..
+                    }),
+                ..
+            }) => EventKind::Resubscribe {
+                user_id: user_id.to_string(),
+                user_name: user_name.to_string(),
+                tier: SubTier::from(tier.clone()),
+                cumulative_months: (*cumulative_months).max(0) as usize,
+                streak_months: streak_months.map(|v| v.max(0) as usize),
+                duration_months: (*duration_months).max(0) as usize,
+                message: message.text.clone(),
+            },
+            Event::ChannelSubscriptionGiftV1(P {
+                message:
+                    M::Notification(ChannelSubscriptionGiftV1Payload {
+                        user_id,
+                        user_name,
+                        tier,
+                        total,
+                        cumulative_total,
+                        is_anonymous,
+                        ..
+                    }),
+                ..
+            }) => EventKind::Subgift {
+                user_id: user_id.as_ref().map(|id| id.to_string()),
+                user_name: username(*is_anonymous, user_name),
+                tier: SubTier::from(tier.clone()),
+                total: (*total).max(0) as usize,
+                cumulative_total: cumulative_total.map(|v| v as usize),
+                is_anonymous: *is_anonymous,
+            },
+            Event::ChannelCheerV1(P {
+                message:
+                    M::Notification(ChannelCheerV1Payload {
+                        user_id,
+                        user_name,
+                        bits,
+                        message,
+                        is_anonymous,
+                        ..
+                    }),
+                ..
+            }) => EventKind::Cheer {
+                user_id: user_id.as_ref().map(|id| id.to_string()),
+                user_name: username(*is_anonymous, user_name),
+                bits: (*bits).max(0) as usize,
+                message: message.clone(),
+                is_anonymous: *is_anonymous,
+            },
+            Event::ChannelPointsCustomRewardRedemptionAddV1(P {
+                message:
+                    M::Notification(ChannelPointsCustomRewardRedemptionAddV1Payload {
+                        user_id,
+                        user_name,
+                        reward,
+                        user_input,
+                        ..
+                    }),
+                ..
+            }) => EventKind::Redemption {
+                user_id: user_id.to_string(),
+                user_name: user_name.to_string(),
+                reward_id: reward.id.to_string(),
+                title: reward.title.clone(),
+                cost: reward.cost.max(0) as usize,
+                user_input: user_input.clone(),
+            },
+            Event::ChannelRaidV1(P {
+                message:
+                    M::Notification(ChannelRaidV1Payload {
+                        from_broadcaster_user_id,
+                        from_broadcaster_user_name,
+                        viewers,
+                        ..
+                    }),
+                ..
+            }) => EventKind::Raid {
+                user_id: from_broadcaster_user_id.to_string(),
+                user_name: from_broadcaster_user_name.to_string(),
+                viewers: (*viewers).max(0) as usize,
+            },
+            Event::StreamOnlineV1(P {
+                message: M::Notification(StreamOnlineV1Payload { id, started_at, .. }),
+                ..
+            }) => EventKind::StreamOnline {
+                stream_id: id.clone(),
+                started_at: started_at.to_string(),
+            },
+            Event::StreamOfflineV1(P {
+                message: M::Notification(_),
+                ..
+            }) => EventKind::StreamOffline,
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+}
+
+/// Renders a row of the events table the same way live events are sent.
+pub fn stored_event_json(event: &tables::events::Event) -> Result<String, serde_json::Error> {
+    let mut payload: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(event.payload.as_deref().unwrap_or("{}"))?;
+    payload.insert("id".to_string(), event.id.into());
+
+    serde_json::to_string(&payload)
+}
+
+/// In-process fan-out of the notifications handled by the eventsub endpoint.
+/// Events are journaled before being published so their id can be used as a
+/// replay cursor.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<LiveEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: LiveEvent) {
+        tracing::debug!(event = event.name(), id = event.id, "publishing live event");
+
+        // an error only means nobody is listening right now
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+}
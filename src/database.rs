@@ -1,8 +1,13 @@
 use libsql::{Connection, Result};
 use std::sync::Arc;
+use tables::{transaction::Transaction, OrmError};
 
 use crate::env::Environment;
 
+/// How long a local connection waits on another one holding the write lock
+/// (e.g. a transaction) before failing with "database is locked".
+const BUSY_TIMEOUT_MS: u64 = 5000;
+
 #[derive(Clone)]
 pub enum Database {
     Local((Arc<libsql::Database>, Connection)),
@@ -30,7 +35,7 @@ impl Database {
             let db = libsql::Builder::new_local(env.turso_local_db_path.clone())
                 .build()
                 .await?;
-            let conn = db.connect()?;
+            let conn = connect_local(&db).await?;
             Ok(Self::Local((Arc::new(db), conn)))
         }
     }
@@ -54,4 +59,47 @@ impl Database {
         };
         Ok(conn)
     }
+
+    /// Begins a transaction on a connection of its own. The local connection
+    /// `conn` returns is shared by every task, so a transaction opened on it
+    /// would take in their statements too.
+    pub async fn transaction(&self) -> std::result::Result<Transaction, OrmError> {
+        let conn = match self {
+            Self::Local((db, _)) => connect_local(db).await?,
+            Self::Remote(db) => db.connect()?,
+        };
+
+        Transaction::begin(&conn).await
+    }
+}
+
+async fn connect_local(db: &libsql::Database) -> Result<Connection> {
+    let conn = db.connect()?;
+    conn.query(&format!("pragma busy_timeout = {}", BUSY_TIMEOUT_MS), ())
+        .await?;
+    Ok(conn)
+}
+
+#[cfg(test)]
+impl Database {
+    /// A local database with the current schema. It lives in a file, unlike
+    /// `:memory:`, so that every connection to it sees the same data.
+    pub async fn scratch() -> Self {
+        use rand::{distributions::Alphanumeric, Rng};
+
+        let name: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        let path = std::env::temp_dir().join(format!("nost_test_{}.sqlite", name.to_lowercase()));
+
+        let db = libsql::Builder::new_local(path).build().await.unwrap();
+        let conn = connect_local(&db).await.unwrap();
+        conn.execute_batch(include_str!("../migrations/schema.sql"))
+            .await
+            .unwrap();
+
+        Self::Local((Arc::new(db), conn))
+    }
 }
@@ -10,7 +10,19 @@ pub struct Environment {
     pub twitch_moderator_id: String,
     pub twitch_eventsub_callback_url: String,
     pub twitch_user_oauth_callback_url: String,
-    pub discord_webhook_url: Secret,
+    pub notifiers: Vec<String>,
+    pub discord_webhook_url: Option<Secret>,
+    pub discord_reward_ids: Vec<String>,
+    pub matrix_homeserver_url: Option<String>,
+    pub matrix_room_id: Option<String>,
+    pub matrix_access_token: Option<Secret>,
+    pub matrix_reward_ids: Vec<String>,
+    pub slack_webhook_url: Option<Secret>,
+    pub templates_path: Option<String>,
+    pub rules_path: Option<String>,
+    pub api_token: Option<Secret>,
+    /// URL and signing secret of every webhook, in order.
+    pub webhooks: Vec<(String, Secret)>,
     pub airtable_base_id: String,
     pub airtable_api_token: Secret,
     pub dev_mode: bool,
@@ -72,6 +84,37 @@ impl Environment {
         Self::string(key).to_secret()
     }
 
+    fn optional(key: &str) -> Option<String> {
+        let full_key = format!("{}{}", Self::PREFIX, key);
+        std::env::var(&full_key).ok()
+    }
+
+    fn optional_secret(key: &str) -> Option<Secret> {
+        Self::optional(key).map(|v| v.to_secret())
+    }
+
+    /// Comma separated, empty when unset.
+    fn list(key: &str) -> Vec<String> {
+        let full_key = format!("{}{}", Self::PREFIX, key);
+        std::env::var(&full_key)
+            .unwrap_or_default()
+            .split(',')
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect()
+    }
+
+    /// `WEBHOOK_1_URL` and `WEBHOOK_1_SECRET`, then `WEBHOOK_2_…` and so on
+    /// until a URL is missing. Every URL needs its secret.
+    fn webhooks() -> Vec<(String, Secret)> {
+        (1..)
+            .map_while(|n| {
+                Self::optional(&format!("WEBHOOK_{}_URL", n))
+                    .map(|url| (url, Self::secret(&format!("WEBHOOK_{}_SECRET", n))))
+            })
+            .collect()
+    }
+
     pub fn new() -> Self {
         let _ = dotenvy::dotenv();
 
@@ -82,7 +125,21 @@ impl Environment {
         let twitch_moderator_id = Self::string("TWITCH_MODERATOR_ID");
         let twitch_eventsub_callback_url = Self::string("TWITCH_EVENTSUB_CALLBACK_URL");
         let twitch_user_oauth_callback_url = Self::string("TWITCH_USER_OAUTH_CALLBACK_URL");
-        let discord_webhook_url = Self::secret("DISCORD_WEBHOOK_URL");
+        let mut notifiers = Self::list("NOTIFIERS");
+        if notifiers.is_empty() {
+            notifiers.push("discord".to_string());
+        }
+        let discord_webhook_url = Self::optional_secret("DISCORD_WEBHOOK_URL");
+        let discord_reward_ids = Self::list("DISCORD_REWARD_IDS");
+        let matrix_homeserver_url = Self::optional("MATRIX_HOMESERVER_URL");
+        let matrix_room_id = Self::optional("MATRIX_ROOM_ID");
+        let matrix_access_token = Self::optional_secret("MATRIX_ACCESS_TOKEN");
+        let matrix_reward_ids = Self::list("MATRIX_REWARD_IDS");
+        let slack_webhook_url = Self::optional_secret("SLACK_WEBHOOK_URL");
+        let templates_path = Self::optional("TEMPLATES_PATH");
+        let rules_path = Self::optional("RULES_PATH");
+        let api_token = Self::optional_secret("API_TOKEN");
+        let webhooks = Self::webhooks();
         let airtable_base_id = Self::string("AIRTABLE_BASE_ID");
         let airtable_api_token = Self::secret("AIRTABLE_API_TOKEN");
         let dev_mode = Self::string("DEV_MODE") == "true";
@@ -98,7 +155,18 @@ impl Environment {
             twitch_moderator_id,
             twitch_eventsub_callback_url,
             twitch_user_oauth_callback_url,
+            notifiers,
             discord_webhook_url,
+            discord_reward_ids,
+            matrix_homeserver_url,
+            matrix_room_id,
+            matrix_access_token,
+            matrix_reward_ids,
+            slack_webhook_url,
+            templates_path,
+            rules_path,
+            api_token,
+            webhooks,
             airtable_base_id,
             airtable_api_token,
             dev_mode,
@@ -1,15 +1,20 @@
-//mod api;
+mod api;
+mod bus;
 mod database;
-mod discord;
 mod env;
 mod models;
+mod notifiers;
+mod rebuild;
 mod tools;
 mod twitch;
 
+use bus::EventBus;
 use database::Database;
 use env::Environment;
 use eyre::Context;
+use notifiers::{Notifiers, Rules};
 use tools::install_tools;
+use twitch::{follows::FollowWatch, profiles::Profiles};
 use twitch_oauth2::Scope;
 
 use std::{net::SocketAddr, process::exit, sync::Arc, time::Duration};
@@ -19,7 +24,7 @@ use axum::{
     http::{header, HeaderValue, Method, StatusCode},
     response::{Html, IntoResponse},
     routing::{get, post},
-    Extension, Router,
+    Extension, Json, Router,
 };
 use tokio::{signal, task::JoinHandle};
 use tower::{BoxError, ServiceBuilder};
@@ -35,11 +40,18 @@ pub struct AppState {
     pub client: HelixClient<'static, reqwest::Client>,
     pub retainer: Arc<retainer::Cache<String, String>>,
     pub database: Arc<Database>,
+    pub bus: EventBus,
+    pub notifiers: Notifiers,
+    pub rules: Rules,
+    pub follows: FollowWatch,
+    pub profiles: Profiles,
 }
 
 #[derive(Debug)]
 pub enum Error {
+    BadRequest(String),
     NotFound(String),
+    Unauthorized(String),
     AppError(anyhow::Error),
 }
 
@@ -48,6 +60,13 @@ async fn main() -> Result<(), eyre::Report> {
     let env = Environment::new();
     install_tools(&env).expect("Failed to install tools");
 
+    if std::env::args().nth(1).as_deref() == Some("rebuild") {
+        let db = Database::new(&env).await?;
+        let force = std::env::args().skip(2).any(|arg| arg == "--force");
+        rebuild::rebuild(&db, force).await?;
+        return Ok(());
+    }
+
     tracing::info!("App starting with:\n{:#?}", env);
 
     let client: HelixClient<reqwest::Client> = HelixClient::default();
@@ -59,6 +78,7 @@ async fn main() -> Result<(), eyre::Report> {
             Scope::ModeratorReadFollowers,
             Scope::ChannelReadSubscriptions,
             Scope::BitsRead,
+            Scope::ChannelReadRedemptions,
         ],
     )
     .await?;
@@ -74,21 +94,34 @@ async fn main() -> Result<(), eyre::Report> {
         Ok::<(), eyre::Report>(())
     });
 
-    let db = Database::new(&env).await.unwrap();
+    let db = Arc::new(Database::new(&env).await?);
+    let profiles = Profiles::new(client.clone(), token.clone(), db.clone());
+    let notifiers = Notifiers::from_env(&env, db.clone(), profiles.clone())?;
+    let rules = Rules::load(env.rules_path.as_deref())
+        .map_err(|e| eyre::eyre!("Invalid notification rules: {:#}", e))?;
 
     let app_state = AppState {
         env: Arc::new(env.clone()),
         token: token.clone(),
         client: client.clone(),
         retainer: retainer.clone(),
-        database: Arc::new(db),
+        database: db,
+        bus: EventBus::new(),
+        notifiers,
+        rules,
+        follows: FollowWatch::default(),
+        profiles,
     };
 
     let cors = CorsLayer::new()
         // .allow_origin("http://localhost:3000".parse::<HeaderValue>().unwrap())
         .allow_origin("*".parse::<HeaderValue>().unwrap())
-        .allow_methods([Method::POST, Method::OPTIONS])
-        .allow_headers(vec![header::CONTENT_TYPE, header::ACCEPT]);
+        .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
+        .allow_headers(vec![
+            header::CONTENT_TYPE,
+            header::ACCEPT,
+            header::AUTHORIZATION,
+        ]);
 
     let error_handler = ServiceBuilder::new()
         .layer(HandleErrorLayer::new(|error: BoxError| async move {
@@ -123,7 +156,7 @@ async fn main() -> Result<(), eyre::Report> {
         // eventsub
         .route("/twitch/eventsub", post(twitch::eventsub::eventsub))
         // api
-        //.nest("/api", api::routes())
+        .nest("/api/v1", api::routes())
         //misc
         .route("/health", get(health))
         .route("/*catchall", get(not_found))
@@ -152,6 +185,15 @@ async fn main() -> Result<(), eyre::Report> {
             .map_err(|e| eyre::eyre!("Server error: {:#}", e))
     });
 
+    let outbox = tokio::spawn(notifiers::outbox::run(app_state.notifiers.clone()));
+    let rules = tokio::spawn(notifiers::rules::watch(app_state.rules.clone()));
+    let profiles_cleanup = tokio::spawn(app_state.profiles.clone().monitor());
+    let follow_waves = tokio::spawn(twitch::follows::run(
+        app_state.follows.clone(),
+        app_state.rules.clone(),
+        app_state.notifiers.clone(),
+    ));
+
     tokio::try_join!(
         flatten(ec_monitor),
         flatten(server),
@@ -161,6 +203,10 @@ async fn main() -> Result<(), eyre::Report> {
             token.clone()
         ))),
         flatten(retainer_cleanup),
+        flatten(outbox),
+        flatten(rules),
+        flatten(follow_waves),
+        flatten(profiles_cleanup),
     )?;
 
     Ok(())
@@ -234,12 +280,28 @@ where
 impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
         match self {
-            Self::NotFound(_) => {
-                (StatusCode::NOT_FOUND, Html("Not found".to_string())).into_response()
-            }
+            Self::BadRequest(e) => (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e })),
+            )
+                .into_response(),
+            Self::NotFound(e) => (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": e })),
+            )
+                .into_response(),
+            Self::Unauthorized(e) => (
+                StatusCode::UNAUTHORIZED,
+                Json(serde_json::json!({ "error": e })),
+            )
+                .into_response(),
             Self::AppError(e) => {
                 tracing::error!("Application error: {:#}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, Html(e.to_string())).into_response()
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": e.to_string() })),
+                )
+                    .into_response()
             }
         }
     }
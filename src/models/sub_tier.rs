@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use twitch_types::SubscriptionTier;
 
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub enum SubTier {
     #[serde(rename = "Tier1")]
     Tier1,
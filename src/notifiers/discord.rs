@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use eyre::Context;
+use serenity::all::{Colour, CreateEmbed, CreateEmbedAuthor, ExecuteWebhook};
+
+use crate::twitch::profiles::Profiles;
+
+use super::{check_response, http_client, Envelope, Notification, Notifier, Templates};
+
+fn channel_url(login: &str) -> String {
+    format!("https://www.twitch.tv/{}", login)
+}
+
+/// Twitch display names only differ from the login by case, unless they use
+/// non-latin characters.
+fn login_from_display_name(username: &str) -> Option<String> {
+    match username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        true => Some(username.to_lowercase()),
+        false => None,
+    }
+}
+
+/// Posts embeds to a Discord webhook. Requests go through reqwest rather than
+/// serenity's `Http` so that a 429 reaches the outbox instead of being slept
+/// through inside serenity's ratelimiter.
+pub struct DiscordNotifier {
+    client: reqwest::Client,
+    embed_color: Colour,
+    profiles: Profiles,
+    reward_ids: Vec<String>,
+    templates: Arc<Templates>,
+    webhook_url: String,
+}
+
+impl DiscordNotifier {
+    /// Only redemptions of `reward_ids` are posted, to keep the channel quiet.
+    pub fn new(
+        webhook_url: String,
+        reward_ids: Vec<String>,
+        templates: Arc<Templates>,
+        profiles: Profiles,
+    ) -> eyre::Result<Self> {
+        url::Url::parse(&webhook_url).wrap_err("Invalid Discord webhook URL")?;
+        let embed_color = Colour::from_rgb(229, 162, 102);
+
+        Ok(Self {
+            client: http_client(),
+            embed_color,
+            profiles,
+            reward_ids,
+            templates,
+            webhook_url,
+        })
+    }
+
+    /// The username linked to the channel, with the avatar as thumbnail. When
+    /// the profile lookup fails the link is guessed from the display name and
+    /// the thumbnail is left out.
+    async fn with_user(&self, embed: CreateEmbed, envelope: &Envelope) -> CreateEmbed {
+        let (Some(username), Some(user_id)) =
+            (envelope.notification.username(), envelope.user_id.as_ref())
+        else {
+            return embed;
+        };
+
+        match self.profiles.get(user_id).await {
+            Some(profile) => {
+                let embed =
+                    embed.author(CreateEmbedAuthor::new(username).url(channel_url(&profile.login)));
+                match profile.profile_image_url {
+                    Some(url) => embed.thumbnail(url),
+                    None => embed,
+                }
+            }
+            None => {
+                let author = CreateEmbedAuthor::new(username);
+                match login_from_display_name(username) {
+                    Some(login) => embed.author(author.url(channel_url(&login))),
+                    None => embed.author(author),
+                }
+            }
+        }
+    }
+
+    async fn send(&self, embed: CreateEmbed) -> anyhow::Result<()> {
+        let builder = ExecuteWebhook::new().embed(embed.color(self.embed_color));
+
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .json(&builder)
+            .send()
+            .await?;
+
+        check_response(response)
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn deliver(&self, envelope: &Envelope) -> anyhow::Result<()> {
+        if let Notification::Redemption { reward_id, .. } = &envelope.notification {
+            if !self.reward_ids.iter().any(|id| id == reward_id) {
+                return Ok(());
+            }
+        }
+
+        let rendered = self.templates.render(&envelope.notification)?;
+
+        let embed = CreateEmbed::default()
+            .title(rendered.title)
+            .description(rendered.body);
+
+        self.send(self.with_user(embed, envelope).await).await
+    }
+}
@@ -0,0 +1,298 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use eyre::Context;
+use serde_json::{json, Value};
+
+use crate::env::Secret;
+
+use super::{check_response, http_client, Envelope, Notification, Notifier, RetryAfter, Templates};
+
+/// Posts `m.room.message` events to a Matrix room through the client-server
+/// API. The envelope id is used as the transaction id, so a delivery retried
+/// by the outbox is deduplicated by the homeserver.
+pub struct MatrixNotifier {
+    client: reqwest::Client,
+    homeserver_url: url::Url,
+    room_id: String,
+    access_token: Secret,
+    reward_ids: Vec<String>,
+    templates: Arc<Templates>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A bold title followed by the body, with a plain text `body` for clients
+/// that do not render HTML.
+fn message(title: &str, body: &str) -> Value {
+    let formatted_body = format!(
+        "<strong>{}</strong><br>{}",
+        escape(title),
+        escape(body).replace('\n', "<br>")
+    );
+
+    json!({
+        "msgtype": "m.text",
+        "body": format!("{}\n{}", title, body),
+        "format": "org.matrix.custom.html",
+        "formatted_body": formatted_body,
+    })
+}
+
+impl MatrixNotifier {
+    /// Only redemptions of `reward_ids` are posted, like on Discord.
+    pub fn new(
+        homeserver_url: String,
+        room_id: String,
+        access_token: Secret,
+        reward_ids: Vec<String>,
+        templates: Arc<Templates>,
+    ) -> eyre::Result<Self> {
+        let homeserver_url =
+            url::Url::parse(&homeserver_url).wrap_err("Invalid Matrix homeserver URL")?;
+        if homeserver_url.cannot_be_a_base() {
+            eyre::bail!(
+                "Invalid Matrix homeserver URL: {} has no path",
+                homeserver_url
+            );
+        }
+
+        Ok(Self {
+            client: http_client(),
+            homeserver_url,
+            room_id,
+            access_token,
+            reward_ids,
+            templates,
+        })
+    }
+
+    fn send_url(&self, txn_id: &str) -> anyhow::Result<url::Url> {
+        let mut url = self.homeserver_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Homeserver URL cannot be a base"))?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room_id,
+                "send",
+                "m.room.message",
+                txn_id,
+            ]);
+        Ok(url)
+    }
+
+    fn content(&self, notification: &Notification) -> anyhow::Result<Option<Value>> {
+        if let Notification::Redemption { reward_id, .. } = notification {
+            if !self.reward_ids.iter().any(|id| id == reward_id) {
+                return Ok(None);
+            }
+        }
+
+        let rendered = self.templates.render(notification)?;
+
+        Ok(Some(message(&rendered.title, &rendered.body)))
+    }
+}
+
+#[async_trait]
+impl Notifier for MatrixNotifier {
+    fn name(&self) -> &'static str {
+        "matrix"
+    }
+
+    fn target(&self) -> String {
+        self.room_id.clone()
+    }
+
+    async fn deliver(&self, envelope: &Envelope) -> anyhow::Result<()> {
+        let Some(content) = self.content(&envelope.notification)? else {
+            return Ok(());
+        };
+
+        let response = self
+            .client
+            .put(self.send_url(&envelope.id)?)
+            .bearer_auth(self.access_token.secret_str())
+            .json(&content)
+            .send()
+            .await?;
+
+        // Homeservers may only give the delay in the body, as `retry_after_ms`.
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+            && !response
+                .headers()
+                .contains_key(reqwest::header::RETRY_AFTER)
+        {
+            let body = response.json::<Value>().await.unwrap_or_default();
+            let retry_after_ms = body["retry_after_ms"].as_u64().unwrap_or(1000);
+
+            return Err(RetryAfter(Duration::from_millis(retry_after_ms)).into());
+        }
+
+        check_response(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        extract::{Path, State},
+        http::{HeaderMap, StatusCode},
+        routing::put,
+        Json, Router,
+    };
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        models::sub_tier::SubTier,
+        notifiers::testing::{envelope, follow, stand_in, templates, Log},
+    };
+
+    #[derive(Debug)]
+    struct Received {
+        room_id: String,
+        txn_id: String,
+        authorization: String,
+        body: Value,
+    }
+
+    /// Stands in for the homeserver: records every request and answers with
+    /// `status` and `body`.
+    async fn homeserver(status: StatusCode, body: Value) -> (String, Log<Received>) {
+        let log: Log<Received> = Log::default();
+        let app = Router::new()
+            .route(
+                "/_matrix/client/v3/rooms/:room_id/send/m.room.message/:txn_id",
+                put(
+                    move |State(log): State<Log<Received>>,
+                          Path((room_id, txn_id)): Path<(String, String)>,
+                          headers: HeaderMap,
+                          Json(received): Json<Value>| async move {
+                        log.lock().unwrap().push(Received {
+                            room_id,
+                            txn_id,
+                            authorization: headers["authorization"].to_str().unwrap().to_string(),
+                            body: received,
+                        });
+                        (status, Json(body))
+                    },
+                ),
+            )
+            .with_state(log.clone());
+
+        (stand_in(app).await, log)
+    }
+
+    fn matrix(url: String) -> MatrixNotifier {
+        MatrixNotifier::new(
+            url,
+            "!room:example.org".to_string(),
+            "token".parse().unwrap(),
+            vec!["hydrate".to_string()],
+            templates(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn deliver() {
+        // arrange
+        let (url, log) = homeserver(StatusCode::OK, json!({ "event_id": "$1" })).await;
+        let matrix = matrix(url);
+
+        // act
+        let res = matrix
+            .deliver(&envelope(Notification::Subscribe {
+                username: "<arinono>".to_string(),
+                tier: SubTier::Tier1,
+            }))
+            .await;
+
+        // assert
+        assert!(res.is_ok());
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].room_id, "!room:example.org".to_string());
+        assert_eq!(log[0].txn_id, "abc123".to_string());
+        assert_eq!(log[0].authorization, "Bearer token".to_string());
+        assert_eq!(
+            log[0].body,
+            json!({
+                "msgtype": "m.text",
+                "body": "New Subscriber\n<arinono> has subscribed to the channel with a tier1 sub!",
+                "format": "org.matrix.custom.html",
+                "formatted_body": "<strong>New Subscriber</strong><br>&lt;arinono&gt; has subscribed to the channel with a tier1 sub!",
+            })
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn deliver_skips_unlisted_rewards() {
+        // arrange
+        let (url, log) = homeserver(StatusCode::OK, json!({})).await;
+        let matrix = matrix(url);
+
+        // act
+        let res = matrix
+            .deliver(&envelope(Notification::Redemption {
+                username: "arinono".to_string(),
+                reward_id: "stretch".to_string(),
+                title: "Stretch".to_string(),
+                cost: 100,
+                input: String::new(),
+            }))
+            .await;
+
+        // assert
+        assert!(res.is_ok());
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn deliver_rate_limited() {
+        // arrange
+        let (url, _) = homeserver(
+            StatusCode::TOO_MANY_REQUESTS,
+            json!({ "errcode": "M_LIMIT_EXCEEDED", "retry_after_ms": 2500 }),
+        )
+        .await;
+        let matrix = matrix(url);
+
+        // act
+        let res = matrix.deliver(&envelope(follow())).await;
+
+        // assert
+        assert!(res.is_err());
+        let err = res.unwrap_err();
+        let retry_after = err.downcast_ref::<RetryAfter>().unwrap();
+        assert_eq!(retry_after.0, Duration::from_millis(2500));
+    }
+    #[test]
+    #[traced_test]
+    fn new_without_base() {
+        // act
+        let res = MatrixNotifier::new(
+            "mailto:matrix@example.org".to_string(),
+            "!room:example.org".to_string(),
+            "token".parse().unwrap(),
+            vec![],
+            templates(),
+        );
+
+        // assert
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,272 @@
+mod discord;
+mod matrix;
+mod notification;
+pub mod outbox;
+pub mod rules;
+mod slack;
+mod templates;
+#[cfg(test)]
+mod testing;
+mod webhook;
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use tables::{outbox::OutboxMessage, recaps::Recap, timestamp};
+use tokio::sync::Notify;
+
+use crate::{
+    database::Database, env::Environment, models::sub_tier::SubTier, twitch::profiles::Profiles,
+};
+
+pub use discord::DiscordNotifier;
+pub use matrix::MatrixNotifier;
+pub use notification::{Envelope, Notification};
+pub use rules::Rules;
+pub use slack::SlackNotifier;
+pub use templates::Templates;
+pub use webhook::{WebhookNotifier, WebhookTarget};
+
+/// Returned by a sink that got rate limited, the outbox retries the delivery
+/// once the delay has passed instead of backing off. It still counts as an
+/// attempt.
+#[derive(Debug, thiserror::Error)]
+#[error("rate limited, retry after {0:?}")]
+pub struct RetryAfter(pub Duration);
+
+/// How long a sink gets to answer, so a hung one can't hold its queue forever.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The client every sink sends its requests with.
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Turns a 429 into `RetryAfter` and any other non 2xx status into an error.
+pub fn check_response(response: reqwest::Response) -> anyhow::Result<()> {
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(1.0);
+
+        return Err(RetryAfter(Duration::from_secs_f64(retry_after)).into());
+    }
+
+    response.error_for_status()?;
+
+    Ok(())
+}
+
+/// A sink that gets told about channel events.
+///
+/// Sinks either implement the per-kind methods they care about, the others
+/// doing nothing, or take over `deliver` to get the whole envelope.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Tells apart sinks of the same kind, e.g. one per webhook URL.
+    fn target(&self) -> String {
+        "default".to_string()
+    }
+
+    async fn deliver(&self, envelope: &Envelope) -> anyhow::Result<()> {
+        envelope.notification.dispatch(self).await
+    }
+
+    async fn new_follower(&self, _username: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn new_subscriber(&self, _username: &str, _tier: &SubTier) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn subgift(&self, _username: &str, _total: usize, _tier: &SubTier) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn bits(&self, _username: &str, _bits: usize, _message: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn resubscriber(
+        &self,
+        _username: &str,
+        _tier: &SubTier,
+        _cumulative_months: usize,
+        _streak_months: Option<usize>,
+        _message: &str,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn redemption(
+        &self,
+        _username: &str,
+        _reward_id: &str,
+        _title: &str,
+        _cost: usize,
+        _input: &str,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn raid(&self, _username: &str, _viewers: usize) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn recap(&self, _recap: &Recap) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn follow_wave(&self, _follows: usize, _suspects: usize) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Every enabled sink. Notifications are queued in the outbox, one row per
+/// sink, and delivered by `outbox::run`.
+#[derive(Clone)]
+pub struct Notifiers {
+    sinks: Vec<Arc<dyn Notifier>>,
+    database: Arc<Database>,
+    wake: Arc<Notify>,
+}
+
+impl Notifiers {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self {
+            sinks: vec![],
+            database,
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Builds the sinks listed in `NOST_NOTIFIERS`, with the wording from
+    /// `NOST_TEMPLATES_PATH` when set. Fails on an unknown sink or one that is
+    /// missing its configuration.
+    pub fn from_env(
+        env: &Environment,
+        database: Arc<Database>,
+        profiles: Profiles,
+    ) -> eyre::Result<Self> {
+        let mut notifiers = Self::new(database);
+        let templates = Templates::load(env.templates_path.as_deref())
+            .map_err(|e| eyre::eyre!("Invalid notification templates: {:#}", e))?;
+        let templates = Arc::new(templates);
+
+        for name in &env.notifiers {
+            match name.as_str() {
+                "discord" => {
+                    let webhook_url = env.discord_webhook_url.as_ref().ok_or_else(|| {
+                        eyre::eyre!("NOST_DISCORD_WEBHOOK_URL is required by the discord notifier")
+                    })?;
+                    notifiers.register(Arc::new(DiscordNotifier::new(
+                        webhook_url.secret_str().to_owned(),
+                        env.discord_reward_ids.clone(),
+                        templates.clone(),
+                        profiles.clone(),
+                    )?));
+                }
+                "matrix" => {
+                    let (Some(homeserver_url), Some(room_id), Some(access_token)) = (
+                        env.matrix_homeserver_url.as_ref(),
+                        env.matrix_room_id.as_ref(),
+                        env.matrix_access_token.as_ref(),
+                    ) else {
+                        eyre::bail!("NOST_MATRIX_HOMESERVER_URL, NOST_MATRIX_ROOM_ID and NOST_MATRIX_ACCESS_TOKEN are required by the matrix notifier");
+                    };
+                    notifiers.register(Arc::new(MatrixNotifier::new(
+                        homeserver_url.clone(),
+                        room_id.clone(),
+                        access_token.clone(),
+                        env.matrix_reward_ids.clone(),
+                        templates.clone(),
+                    )?));
+                }
+                "slack" => {
+                    let webhook_url = env.slack_webhook_url.as_ref().ok_or_else(|| {
+                        eyre::eyre!("NOST_SLACK_WEBHOOK_URL is required by the slack notifier")
+                    })?;
+                    notifiers.register(Arc::new(SlackNotifier::new(
+                        webhook_url.secret_str().to_owned(),
+                        templates.clone(),
+                    )?));
+                }
+                "webhook" => {
+                    if env.webhooks.is_empty() {
+                        eyre::bail!("NOST_WEBHOOK_1_URL is required by the webhook notifier");
+                    }
+                    for (url, secret) in &env.webhooks {
+                        notifiers.register(Arc::new(WebhookNotifier::new(WebhookTarget {
+                            url: url.clone(),
+                            secret: secret.clone(),
+                        })));
+                    }
+                }
+                other => eyre::bail!("Unknown notifier: {}", other),
+            }
+        }
+
+        Ok(notifiers)
+    }
+
+    pub fn register(&mut self, notifier: Arc<dyn Notifier>) {
+        self.sinks.push(notifier);
+    }
+
+    pub fn get(&self, name: &str, target: &str) -> Option<Arc<dyn Notifier>> {
+        self.sinks
+            .iter()
+            .find(|sink| sink.name() == name && sink.target() == target)
+            .cloned()
+    }
+
+    /// Queues the notification for every sink and wakes the outbox worker.
+    /// `user_id` is the Twitch id of whoever triggered the event, if known.
+    pub async fn notify(&self, user_id: Option<&str>, notification: Notification) {
+        let envelope = Envelope {
+            id: format!("{:032x}", rand::random::<u128>()),
+            timestamp: timestamp::format(&Utc::now()),
+            user_id: user_id.map(str::to_string),
+            notification,
+        };
+        let payload = match serde_json::to_string(&envelope) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::error!("Failed to serialize notification: {}", e);
+                return;
+            }
+        };
+        let conn = match self.database.conn() {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::error!("Failed to queue notification {}: {}", envelope.id, e);
+                return;
+            }
+        };
+
+        for sink in &self.sinks {
+            let message =
+                OutboxMessage::from(sink.name().to_string(), sink.target(), payload.clone());
+            if let Err(e) = message.create(&conn).await {
+                tracing::error!(
+                    "Failed to queue notification {} for {}: {}",
+                    envelope.id,
+                    sink.name(),
+                    e
+                );
+            }
+        }
+
+        self.wake.notify_one();
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use tables::recaps::Recap;
+
+use crate::models::sub_tier::SubTier;
+
+use super::Notifier;
+
+/// Every `Notification::kind`.
+pub const KINDS: [&str; 9] = [
+    "follow",
+    "subscribe",
+    "resubscribe",
+    "subgift",
+    "bits",
+    "redemption",
+    "raid",
+    "recap",
+    "follow_wave",
+];
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum Notification {
+    Follow {
+        username: String,
+    },
+    Subscribe {
+        username: String,
+        tier: SubTier,
+    },
+    Resubscribe {
+        username: String,
+        tier: SubTier,
+        cumulative_months: usize,
+        streak_months: Option<usize>,
+        message: String,
+    },
+    Subgift {
+        username: String,
+        total: usize,
+        tier: SubTier,
+    },
+    Bits {
+        username: String,
+        bits: usize,
+        message: String,
+    },
+    Redemption {
+        username: String,
+        reward_id: String,
+        title: String,
+        cost: usize,
+        input: String,
+    },
+    Raid {
+        username: String,
+        viewers: usize,
+    },
+    Recap(Recap),
+    /// Sent once a follow-bot wave is over, in place of its follows.
+    FollowWave {
+        follows: usize,
+        suspects: usize,
+        started_at: String,
+        ended_at: String,
+    },
+}
+
+/// What gets persisted in the outbox: the notification plus an id and a
+/// timestamp shared by every sink it is fanned out to.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Envelope {
+    pub id: String,
+    pub timestamp: String,
+    /// Twitch id of whoever triggered the event, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    #[serde(flatten)]
+    pub notification: Notification,
+}
+
+impl Notification {
+    /// The `type` it is serialized with.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Notification::Follow { .. } => "follow",
+            Notification::Subscribe { .. } => "subscribe",
+            Notification::Resubscribe { .. } => "resubscribe",
+            Notification::Subgift { .. } => "subgift",
+            Notification::Bits { .. } => "bits",
+            Notification::Redemption { .. } => "redemption",
+            Notification::Raid { .. } => "raid",
+            Notification::Recap(_) => "recap",
+            Notification::FollowWave { .. } => "follow_wave",
+        }
+    }
+
+    /// Who triggered the event, for the kinds that have someone.
+    pub fn username(&self) -> Option<&str> {
+        match self {
+            Notification::Follow { username }
+            | Notification::Subscribe { username, .. }
+            | Notification::Resubscribe { username, .. }
+            | Notification::Subgift { username, .. }
+            | Notification::Bits { username, .. }
+            | Notification::Redemption { username, .. }
+            | Notification::Raid { username, .. } => Some(username),
+            Notification::Recap(_) | Notification::FollowWave { .. } => None,
+        }
+    }
+
+    pub async fn dispatch(&self, notifier: &(impl Notifier + ?Sized)) -> anyhow::Result<()> {
+        match self {
+            Notification::Follow { username } => notifier.new_follower(username).await,
+            Notification::Subscribe { username, tier } => {
+                notifier.new_subscriber(username, tier).await
+            }
+            Notification::Resubscribe {
+                username,
+                tier,
+                cumulative_months,
+                streak_months,
+                message,
+            } => {
+                notifier
+                    .resubscriber(username, tier, *cumulative_months, *streak_months, message)
+                    .await
+            }
+            Notification::Subgift {
+                username,
+                total,
+                tier,
+            } => notifier.subgift(username, *total, tier).await,
+            Notification::Bits {
+                username,
+                bits,
+                message,
+            } => notifier.bits(username, *bits, message).await,
+            Notification::Redemption {
+                username,
+                reward_id,
+                title,
+                cost,
+                input,
+            } => {
+                notifier
+                    .redemption(username, reward_id, title, *cost, input)
+                    .await
+            }
+            Notification::Raid { username, viewers } => notifier.raid(username, *viewers).await,
+            Notification::Recap(recap) => notifier.recap(recap).await,
+            Notification::FollowWave {
+                follows, suspects, ..
+            } => notifier.follow_wave(*follows, *suspects).await,
+        }
+    }
+}
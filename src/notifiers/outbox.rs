@@ -0,0 +1,330 @@
+use std::{collections::HashMap, time::Duration};
+
+use tables::{dead_letters::DeadLetter, outbox::OutboxMessage, OrmError};
+
+use super::{Envelope, Notifiers, RetryAfter};
+
+const BATCH: u64 = 50;
+const POLL: Duration = Duration::from_secs(5);
+const MAX_ATTEMPTS: u32 = 8;
+const BACKOFF_SECS: f64 = 2.0;
+const MAX_BACKOFF_SECS: f64 = 600.0;
+
+/// Delivers queued notifications until the process stops. Messages are only
+/// removed from the outbox once delivered (or dead-lettered), so whatever was
+/// pending on shutdown is picked up again on the next start.
+pub async fn run(notifiers: Notifiers) -> Result<(), eyre::Report> {
+    let mut failures = 0;
+
+    loop {
+        match drain(&notifiers).await {
+            Ok(0) => {
+                failures = 0;
+                tokio::select! {
+                    _ = notifiers.wake.notified() => {}
+                    _ = tokio::time::sleep(POLL) => {}
+                }
+            }
+            Ok(_) => failures = 0,
+            // a message left in the outbox after being sent would be sent
+            // again on every pass, so back off until the database recovers
+            Err(e) => {
+                failures += 1;
+                tracing::error!("Failed to drain the outbox: {}", e);
+                tokio::time::sleep(Duration::from_secs_f64(backoff(failures))).await;
+            }
+        }
+    }
+}
+
+/// Delivers the due messages and returns how many were settled, i.e.
+/// deleted or rescheduled. Fails if any of them could not be updated.
+///
+/// Each sink target gets its own queue, delivered in order, and the queues
+/// run side by side so a slow sink only delays its own messages.
+async fn drain(notifiers: &Notifiers) -> Result<usize, OrmError> {
+    let conn = notifiers.database.conn()?;
+    let due = OutboxMessage::due(&conn, BATCH).await?;
+
+    let mut queues: HashMap<(&str, &str), Vec<&OutboxMessage>> = HashMap::new();
+    for message in &due {
+        queues
+            .entry((&message.sink, &message.target))
+            .or_default()
+            .push(message);
+    }
+
+    let results = futures::future::join_all(queues.into_values().map(|queue| async move {
+        let conn = notifiers.database.conn()?;
+        let mut settled = 0;
+        let mut failed = None;
+        for message in queue {
+            match deliver(notifiers, &conn, message).await {
+                Ok(()) => settled += 1,
+                Err(e) => {
+                    tracing::error!("Failed to update outbox message {}: {}", message.id, e);
+                    failed = Some(e);
+                }
+            }
+        }
+
+        match failed {
+            Some(e) => Err(e),
+            None => Ok(settled),
+        }
+    }))
+    .await;
+
+    results.into_iter().sum()
+}
+
+fn backoff(attempts: u32) -> f64 {
+    (BACKOFF_SECS * 2f64.powi(attempts as i32 - 1)).min(MAX_BACKOFF_SECS)
+}
+
+async fn deliver(
+    notifiers: &Notifiers,
+    conn: &libsql::Connection,
+    message: &OutboxMessage,
+) -> Result<(), OrmError> {
+    let attempts = message.attempts + 1;
+
+    let Some(sink) = notifiers.get(&message.sink, &message.target) else {
+        return dead_letter(conn, message, attempts, "Sink is no longer configured").await;
+    };
+    let envelope = match serde_json::from_str::<Envelope>(&message.payload) {
+        Ok(envelope) => envelope,
+        Err(e) => return dead_letter(conn, message, attempts, &e.to_string()).await,
+    };
+
+    let e = match sink.deliver(&envelope).await {
+        Ok(()) => return OutboxMessage::delete(conn, message.id).await,
+        Err(e) => e,
+    };
+
+    // rate limits count as attempts too, or a sink that keeps answering 429
+    // would hold on to the message forever
+    let delay = match e.downcast_ref::<RetryAfter>() {
+        Some(RetryAfter(delay)) => {
+            tracing::warn!(
+                "Notification {} to {} rate limited for {:?} (attempt {}/{})",
+                envelope.id,
+                message.sink,
+                delay,
+                attempts,
+                MAX_ATTEMPTS
+            );
+            delay.as_secs_f64()
+        }
+        None => {
+            tracing::warn!(
+                "Notification {} to {} failed (attempt {}/{}): {:#}",
+                envelope.id,
+                message.sink,
+                attempts,
+                MAX_ATTEMPTS,
+                e
+            );
+            backoff(attempts)
+        }
+    };
+
+    if attempts >= MAX_ATTEMPTS {
+        return dead_letter(conn, message, attempts, &format!("{:#}", e)).await;
+    }
+
+    OutboxMessage::reschedule(conn, message.id, attempts, delay, &format!("{:#}", e)).await
+}
+
+async fn dead_letter(
+    conn: &libsql::Connection,
+    message: &OutboxMessage,
+    attempts: u32,
+    error: &str,
+) -> Result<(), OrmError> {
+    let mut dead_letter = DeadLetter::from(
+        message.sink.clone(),
+        message.target.clone(),
+        message.payload.clone(),
+        attempts,
+    );
+    dead_letter.error = Some(error.to_string());
+    dead_letter.create(conn).await?;
+
+    OutboxMessage::delete(conn, message.id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use libsql::Connection;
+    use tables::{params, Orm};
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        database::Database,
+        notifiers::{
+            testing::{envelope, follow},
+            Notifier,
+        },
+    };
+
+    enum Answer {
+        Fail,
+        RateLimited(Duration),
+    }
+
+    /// A sink that never gets the message through.
+    struct Stub(Answer);
+
+    #[async_trait]
+    impl Notifier for Stub {
+        fn name(&self) -> &'static str {
+            "stub"
+        }
+
+        async fn deliver(&self, _envelope: &Envelope) -> anyhow::Result<()> {
+            match self.0 {
+                Answer::Fail => Err(anyhow::anyhow!("boom")),
+                Answer::RateLimited(delay) => Err(RetryAfter(delay).into()),
+            }
+        }
+    }
+
+    async fn notifiers(answer: Answer) -> (Notifiers, Connection) {
+        let db = libsql::Builder::new_local(":memory:")
+            .build()
+            .await
+            .unwrap();
+        let conn = db.connect().unwrap();
+        conn.execute_batch(include_str!("../../migrations/schema.sql"))
+            .await
+            .unwrap();
+
+        let mut notifiers = Notifiers::new(Arc::new(Database::Local((Arc::new(db), conn.clone()))));
+        notifiers.register(Arc::new(Stub(answer)));
+
+        (notifiers, conn)
+    }
+
+    async fn queue(conn: &Connection, sink: &str) -> OutboxMessage {
+        let id = OutboxMessage::from(
+            sink.to_string(),
+            "default".to_string(),
+            serde_json::to_string(&envelope(follow())).unwrap(),
+        )
+        .create(conn)
+        .await
+        .unwrap();
+
+        stored(conn, id).await.unwrap()
+    }
+
+    async fn stored(conn: &Connection, id: u64) -> Option<OutboxMessage> {
+        Orm::<OutboxMessage>::query(conn, "select * from outbox where id = ?1", params![id])
+            .await
+            .unwrap()
+            .into_iter()
+            .next()
+    }
+
+    /// Seconds from now until the message is due again.
+    fn due_in(message: &OutboxMessage) -> f64 {
+        (message.available_at - Utc::now()).num_milliseconds() as f64 / 1000.0
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn deliver_failed() {
+        // arrange
+        let (notifiers, conn) = notifiers(Answer::Fail).await;
+        let message = queue(&conn, "stub").await;
+
+        // act
+        let first = deliver(&notifiers, &conn, &message).await;
+        let once = stored(&conn, message.id).await.unwrap();
+        let second = deliver(&notifiers, &conn, &once).await;
+        let twice = stored(&conn, message.id).await.unwrap();
+
+        // assert
+        assert!(first.is_ok());
+        assert_eq!(once.attempts, 1);
+        assert_eq!(once.error, Some("boom".to_string()));
+        assert!((1.0..=2.0).contains(&due_in(&once)));
+        assert!(second.is_ok());
+        assert_eq!(twice.attempts, 2);
+        assert!((3.0..=4.0).contains(&due_in(&twice)));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn deliver_rate_limited() {
+        // arrange
+        let (notifiers, conn) = notifiers(Answer::RateLimited(Duration::from_secs(30))).await;
+        let message = queue(&conn, "stub").await;
+
+        // act
+        let res = deliver(&notifiers, &conn, &message).await;
+
+        // assert
+        assert!(res.is_ok());
+        let message = stored(&conn, message.id).await.unwrap();
+        assert_eq!(message.attempts, 1);
+        assert!((29.0..=30.0).contains(&due_in(&message)));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn deliver_dead_letters() {
+        // arrange
+        let (notifiers, conn) = notifiers(Answer::RateLimited(Duration::from_secs(30))).await;
+        let message = queue(&conn, "stub").await;
+        OutboxMessage::reschedule(&conn, message.id, MAX_ATTEMPTS - 1, 0.0, "")
+            .await
+            .unwrap();
+        let message = stored(&conn, message.id).await.unwrap();
+
+        // act
+        let res = deliver(&notifiers, &conn, &message).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert!(stored(&conn, message.id).await.is_none());
+        let dead_letters = DeadLetter::list(&conn, 10, 0).await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].sink, "stub".to_string());
+        assert_eq!(dead_letters[0].attempts, MAX_ATTEMPTS);
+        assert_eq!(dead_letters[0].payload, message.payload);
+        assert_eq!(
+            dead_letters[0].error,
+            Some("rate limited, retry after 30s".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn deliver_sink_gone() {
+        // arrange
+        let (notifiers, conn) = notifiers(Answer::Fail).await;
+        let message = queue(&conn, "discord").await;
+
+        // act
+        let res = deliver(&notifiers, &conn, &message).await;
+
+        // assert
+        assert!(res.is_ok());
+        assert!(stored(&conn, message.id).await.is_none());
+        let dead_letters = DeadLetter::list(&conn, 10, 0).await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].sink, "discord".to_string());
+        assert_eq!(dead_letters[0].attempts, 1);
+        assert_eq!(
+            dead_letters[0].error,
+            Some("Sink is no longer configured".to_string())
+        );
+    }
+}
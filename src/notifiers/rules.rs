@@ -0,0 +1,318 @@
+use std::{
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::models::sub_tier::SubTier;
+
+use super::{notification::KINDS, Notification};
+
+const POLL: Duration = Duration::from_secs(5);
+
+/// Which events get notified, e.g.
+///
+/// ```toml
+/// disabled = ["follow"]
+/// ignored_user_ids = ["123456789"]
+/// min_bits = 100
+/// min_subgifts = 5
+/// tiers = ["Tier2", "Tier3"]
+/// ```
+///
+/// `tiers` applies to subs, resubs and sub gifts, every tier passes when it
+/// is left out.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    #[serde(default)]
+    pub ignored_user_ids: Vec<String>,
+    #[serde(default)]
+    pub min_bits: usize,
+    #[serde(default)]
+    pub min_subgifts: usize,
+    pub tiers: Option<Vec<SubTier>>,
+}
+
+impl RuleSet {
+    fn parse(source: &str) -> anyhow::Result<Self> {
+        let rules: RuleSet = toml::from_str(source)?;
+
+        if let Some(kind) = rules
+            .disabled
+            .iter()
+            .find(|kind| !KINDS.contains(&kind.as_str()))
+        {
+            anyhow::bail!("unknown notification kind `{}`", kind);
+        }
+
+        Ok(rules)
+    }
+
+    fn tier(&self, tier: &SubTier) -> bool {
+        self.tiers.as_ref().is_none_or(|tiers| tiers.contains(tier))
+    }
+
+    /// `user_id` is the Twitch id of whoever triggered the event, if known.
+    pub fn allows(&self, notification: &Notification, user_id: Option<&str>) -> bool {
+        if self.disabled.iter().any(|kind| kind == notification.kind()) {
+            return false;
+        }
+
+        if let Some(user_id) = user_id {
+            if self.ignored_user_ids.iter().any(|id| id == user_id) {
+                return false;
+            }
+        }
+
+        match notification {
+            Notification::Subscribe { tier, .. } | Notification::Resubscribe { tier, .. } => {
+                self.tier(tier)
+            }
+            Notification::Subgift { total, tier, .. } => {
+                *total >= self.min_subgifts && self.tier(tier)
+            }
+            Notification::Bits { bits, .. } => *bits >= self.min_bits,
+            _ => true,
+        }
+    }
+}
+
+/// The rule set read from `NOST_RULES_PATH`, everything passes when it is
+/// unset. `watch` reloads the file whenever it changes, so rules can be
+/// edited without a restart.
+#[derive(Clone, Default)]
+pub struct Rules {
+    path: Option<String>,
+    current: Arc<RwLock<RuleSet>>,
+    modified: Arc<Mutex<Option<SystemTime>>>,
+}
+
+fn modified(path: &str) -> anyhow::Result<SystemTime> {
+    let metadata = std::fs::metadata(path).with_context(|| format!("Failed to read {}", path))?;
+
+    Ok(metadata.modified()?)
+}
+
+impl Rules {
+    pub fn load(path: Option<&str>) -> anyhow::Result<Self> {
+        let rules = Self {
+            path: path.map(str::to_string),
+            ..Default::default()
+        };
+        rules.reload()?;
+
+        Ok(rules)
+    }
+
+    /// Re-reads the file if it changed since the last call. An invalid file
+    /// leaves the current rules in place.
+    pub fn reload(&self) -> anyhow::Result<bool> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+
+        let modified_at = modified(path)?;
+        {
+            let mut last = self.modified.lock().unwrap();
+            if *last == Some(modified_at) {
+                return Ok(false);
+            }
+            *last = Some(modified_at);
+        }
+
+        let source =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+        let rules =
+            RuleSet::parse(&source).with_context(|| format!("Invalid rules in {}", path))?;
+        *self.current.write().unwrap() = rules;
+
+        Ok(true)
+    }
+
+    pub fn allows(&self, notification: &Notification, user_id: Option<&str>) -> bool {
+        self.current.read().unwrap().allows(notification, user_id)
+    }
+}
+
+/// Polls the rules file for changes until the process stops.
+pub async fn watch(rules: Rules) -> Result<(), eyre::Report> {
+    if rules.path.is_none() {
+        return Ok(());
+    }
+
+    loop {
+        tokio::time::sleep(POLL).await;
+
+        match rules.reload() {
+            Ok(true) => tracing::info!("Reloaded notification rules"),
+            Ok(false) => {}
+            Err(e) => tracing::error!(
+                "Failed to reload notification rules, keeping the previous ones: {:#}",
+                e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::notifiers::testing::follow;
+
+    fn write(path: &std::path::Path, source: &str) {
+        std::fs::write(path, source).unwrap();
+    }
+
+    fn temp_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("nost-rules-{:016x}.toml", rand::random::<u64>()))
+    }
+
+    fn bits(bits: usize) -> Notification {
+        Notification::Bits {
+            username: "arinono".to_string(),
+            bits,
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn allows_everything_by_default() {
+        // arrange
+        let rules = Rules::load(None).unwrap();
+
+        // act
+        let res = rules.allows(&bits(1), Some("123"));
+
+        // assert
+        assert!(res);
+    }
+
+    #[test]
+    #[traced_test]
+    fn allows_with_thresholds() {
+        // arrange
+        let rules = RuleSet::parse(
+            r#"
+min_bits = 100
+min_subgifts = 5
+tiers = ["Tier2", "Tier3"]
+"#,
+        )
+        .unwrap();
+        let subgift = |total, tier| Notification::Subgift {
+            username: "arinono".to_string(),
+            total,
+            tier,
+        };
+
+        // act
+        let res = [
+            rules.allows(&bits(99), None),
+            rules.allows(&bits(100), None),
+            rules.allows(&subgift(4, SubTier::Tier2), None),
+            rules.allows(&subgift(5, SubTier::Tier1), None),
+            rules.allows(&subgift(5, SubTier::Tier3), None),
+            rules.allows(
+                &Notification::Subscribe {
+                    username: "arinono".to_string(),
+                    tier: SubTier::Prime,
+                },
+                None,
+            ),
+        ];
+
+        // assert
+        assert_eq!(res, [false, true, false, false, true, false]);
+    }
+
+    #[test]
+    #[traced_test]
+    fn allows_with_disabled_kinds_and_ignored_users() {
+        // arrange
+        let rules = RuleSet::parse(
+            r#"
+disabled = ["follow"]
+ignored_user_ids = ["666"]
+"#,
+        )
+        .unwrap();
+        let follow = follow();
+
+        // act
+        let res = [
+            rules.allows(&follow, Some("123")),
+            rules.allows(&bits(1), Some("666")),
+            rules.allows(&bits(1), Some("123")),
+            rules.allows(&bits(1), None),
+        ];
+
+        // assert
+        assert_eq!(res, [false, false, true, true]);
+    }
+
+    #[test]
+    #[traced_test]
+    fn parse_unknown_kind() {
+        // act
+        let res = RuleSet::parse(r#"disabled = ["host"]"#);
+
+        // assert
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "unknown notification kind `host`".to_string()
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn reload() {
+        // arrange
+        let path = temp_path();
+        write(&path, "min_bits = 100");
+        let rules = Rules::load(path.to_str()).unwrap();
+        assert!(!rules.allows(&bits(50), None));
+
+        // act
+        write(&path, "min_bits = 10");
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        let res = rules.reload();
+
+        // assert
+        assert!(res.is_ok());
+        assert!(res.unwrap());
+        assert!(rules.allows(&bits(50), None));
+        assert!(!rules.reload().unwrap());
+    }
+
+    #[test]
+    #[traced_test]
+    fn reload_keeps_rules_on_error() {
+        // arrange
+        let path = temp_path();
+        write(&path, "min_bits = 100");
+        let rules = Rules::load(path.to_str()).unwrap();
+
+        // act
+        write(&path, "min_bits = \"lots\"");
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        let res = rules.reload();
+
+        // assert
+        assert!(res.is_err());
+        assert!(!rules.allows(&bits(50), None));
+        assert!(rules.allows(&bits(100), None));
+    }
+}
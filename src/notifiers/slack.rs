@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use eyre::Context;
+use serde_json::{json, Value};
+
+use super::{check_response, http_client, Envelope, Notification, Notifier, Templates};
+
+/// Posts Block Kit messages to a Slack incoming webhook.
+pub struct SlackNotifier {
+    client: reqwest::Client,
+    templates: Arc<Templates>,
+    webhook_url: String,
+}
+
+/// Slack only wants `&`, `<` and `>` escaped in text objects.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// A header followed by a section holding the body, the Slack counterpart of
+/// a Discord embed. `text` is the notification fallback.
+fn message(title: &str, body: &str) -> Value {
+    json!({
+        "text": title,
+        "blocks": [
+            {
+                "type": "header",
+                "text": { "type": "plain_text", "text": title },
+            },
+            {
+                "type": "section",
+                "text": { "type": "mrkdwn", "text": escape(body) },
+            },
+        ],
+    })
+}
+
+impl SlackNotifier {
+    pub fn new(webhook_url: String, templates: Arc<Templates>) -> eyre::Result<Self> {
+        url::Url::parse(&webhook_url).wrap_err("Invalid Slack webhook URL")?;
+
+        Ok(Self {
+            client: http_client(),
+            templates,
+            webhook_url,
+        })
+    }
+
+    async fn send(&self, message: Value) -> anyhow::Result<()> {
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .json(&message)
+            .send()
+            .await?;
+
+        check_response(response)
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    /// Only follows, subs, sub gifts and bits are posted.
+    async fn deliver(&self, envelope: &Envelope) -> anyhow::Result<()> {
+        match &envelope.notification {
+            Notification::Follow { .. }
+            | Notification::Subscribe { .. }
+            | Notification::Subgift { .. }
+            | Notification::Bits { .. } => {}
+            _ => return Ok(()),
+        }
+
+        let rendered = self.templates.render(&envelope.notification)?;
+
+        self.send(message(&rendered.title, &rendered.body)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        models::sub_tier::SubTier,
+        notifiers::{
+            testing::{envelope, follow, stand_in, templates, Log},
+            RetryAfter,
+        },
+    };
+
+    /// Stands in for Slack: records every body and answers with `status`.
+    async fn slack_api(status: StatusCode) -> (String, Log<Value>) {
+        let received: Log<Value> = Log::default();
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(
+                    move |State(received): State<Log<Value>>, Json(body): Json<Value>| async move {
+                        received.lock().unwrap().push(body);
+                        (status, [("retry-after", "3")])
+                    },
+                ),
+            )
+            .with_state(received.clone());
+
+        (format!("{}/hook", stand_in(app).await), received)
+    }
+
+    fn slack(url: String) -> SlackNotifier {
+        SlackNotifier::new(url, templates()).unwrap()
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn new_follower() {
+        // arrange
+        let (url, received) = slack_api(StatusCode::OK).await;
+        let slack = slack(url);
+
+        // act
+        let res = slack.deliver(&envelope(follow())).await;
+
+        // assert
+        assert!(res.is_ok());
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(
+            received[0],
+            json!({
+                "text": "New Follower",
+                "blocks": [
+                    {
+                        "type": "header",
+                        "text": { "type": "plain_text", "text": "New Follower" },
+                    },
+                    {
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": "arinono is now following the channel!" },
+                    },
+                ],
+            })
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn new_subscriber() {
+        // arrange
+        let (url, received) = slack_api(StatusCode::OK).await;
+        let slack = slack(url);
+
+        // act
+        let res = slack
+            .deliver(&envelope(Notification::Subscribe {
+                username: "arinono".to_string(),
+                tier: SubTier::Tier2,
+            }))
+            .await;
+
+        // assert
+        assert!(res.is_ok());
+        let received = received.lock().unwrap();
+        assert_eq!(received[0]["text"], "New Subscriber");
+        assert_eq!(
+            received[0]["blocks"][1]["text"]["text"],
+            "arinono has subscribed to the channel with a tier2 sub!"
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn subgift() {
+        // arrange
+        let (url, received) = slack_api(StatusCode::OK).await;
+        let slack = slack(url);
+
+        // act
+        let res = slack
+            .deliver(&envelope(Notification::Subgift {
+                username: "arinono".to_string(),
+                total: 5,
+                tier: SubTier::Tier1,
+            }))
+            .await;
+
+        // assert
+        assert!(res.is_ok());
+        let received = received.lock().unwrap();
+        assert_eq!(received[0]["text"], "New Sub Gift");
+        assert_eq!(
+            received[0]["blocks"][1]["text"]["text"],
+            "arinono gifted 5 tier1 subs to the community!"
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn bits() {
+        // arrange
+        let (url, received) = slack_api(StatusCode::OK).await;
+        let slack = slack(url);
+
+        // act
+        let res = slack
+            .deliver(&envelope(Notification::Bits {
+                username: "arinono".to_string(),
+                bits: 100,
+                message: "<3 & cheers".to_string(),
+            }))
+            .await;
+
+        // assert
+        assert!(res.is_ok());
+        let received = received.lock().unwrap();
+        assert_eq!(received[0]["text"], "New Bits");
+        assert_eq!(
+            received[0]["blocks"][1]["text"]["text"],
+            "arinono cheered 100 bits!\n&lt;3 &amp; cheers"
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn skips_other_kinds() {
+        // arrange
+        let (url, received) = slack_api(StatusCode::OK).await;
+        let slack = slack(url);
+
+        // act
+        let res = slack
+            .deliver(&envelope(Notification::Raid {
+                username: "arinono".to_string(),
+                viewers: 42,
+            }))
+            .await;
+
+        // assert
+        assert!(res.is_ok());
+        assert!(received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn rate_limited() {
+        // arrange
+        let (url, _) = slack_api(StatusCode::TOO_MANY_REQUESTS).await;
+        let slack = slack(url);
+
+        // act
+        let res = slack.deliver(&envelope(follow())).await;
+
+        // assert
+        assert!(res.is_err());
+        let err = res.unwrap_err();
+        let retry_after = err.downcast_ref::<RetryAfter>().unwrap();
+        assert_eq!(retry_after.0.as_secs(), 3);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn server_error() {
+        // arrange
+        let (url, _) = slack_api(StatusCode::INTERNAL_SERVER_ERROR).await;
+        let slack = slack(url);
+
+        // act
+        let res = slack.deliver(&envelope(follow())).await;
+
+        // assert
+        assert!(res.is_err());
+        assert!(res.unwrap_err().downcast_ref::<RetryAfter>().is_none());
+    }
+}
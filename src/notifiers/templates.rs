@@ -0,0 +1,347 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use minijinja::UndefinedBehavior;
+use serde::Deserialize;
+use tables::recaps::{Recap, RecapContributor, RecapTier};
+
+use crate::models::sub_tier::SubTier;
+
+use super::{notification::KINDS, Notification};
+
+const DEFAULTS: &str = include_str!("templates.toml");
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KindTemplates {
+    title: Option<String>,
+    body: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Rendered {
+    pub title: String,
+    pub body: String,
+}
+
+/// The wording of every notification, as a `title` and a `body` template per
+/// kind. Defaults live in `templates.toml`, next to this file, and can be
+/// overridden kind by kind from the file at `NOST_TEMPLATES_PATH`.
+pub struct Templates {
+    env: minijinja::Environment<'static>,
+}
+
+fn parse(source: &str) -> anyhow::Result<BTreeMap<String, KindTemplates>> {
+    let templates: BTreeMap<String, KindTemplates> = toml::from_str(source)?;
+
+    if let Some(kind) = templates
+        .keys()
+        .find(|kind| !KINDS.contains(&kind.as_str()))
+    {
+        anyhow::bail!("unknown notification kind `{}`", kind);
+    }
+
+    Ok(templates)
+}
+
+/// One notification per kind, twice for the kinds with optional fields, so
+/// that both branches of a template get rendered at startup.
+fn samples() -> Vec<Notification> {
+    let username = "arinono".to_string();
+
+    vec![
+        Notification::Follow {
+            username: username.clone(),
+        },
+        Notification::Subscribe {
+            username: username.clone(),
+            tier: SubTier::Tier1,
+        },
+        Notification::Resubscribe {
+            username: username.clone(),
+            tier: SubTier::Tier2,
+            cumulative_months: 12,
+            streak_months: Some(6),
+            message: "Hello!".to_string(),
+        },
+        Notification::Resubscribe {
+            username: username.clone(),
+            tier: SubTier::Prime,
+            cumulative_months: 2,
+            streak_months: None,
+            message: String::new(),
+        },
+        Notification::Subgift {
+            username: username.clone(),
+            total: 5,
+            tier: SubTier::Tier1,
+        },
+        Notification::Bits {
+            username: username.clone(),
+            bits: 100,
+            message: "Cheer100".to_string(),
+        },
+        Notification::Redemption {
+            username: username.clone(),
+            reward_id: "hydrate".to_string(),
+            title: "Hydrate".to_string(),
+            cost: 500,
+            input: "Please".to_string(),
+        },
+        Notification::Raid {
+            username: username.clone(),
+            viewers: 42,
+        },
+        Notification::Recap(Recap {
+            new_followers: 3,
+            subs: vec![RecapTier {
+                tier: "Tier1".to_string(),
+                count: 2,
+            }],
+            subgifts: 5,
+            bits: 100,
+            top_gifter: Some(RecapContributor {
+                name: username.clone(),
+                total: 5,
+            }),
+            top_cheerer: Some(RecapContributor {
+                name: username,
+                total: 100,
+            }),
+        }),
+        Notification::Recap(Recap {
+            new_followers: 0,
+            subs: vec![],
+            subgifts: 0,
+            bits: 0,
+            top_gifter: None,
+            top_cheerer: None,
+        }),
+        Notification::FollowWave {
+            follows: 250,
+            suspects: 240,
+            started_at: "2025-02-10T20:00:00.000Z".to_string(),
+            ended_at: "2025-02-10T20:05:00.000Z".to_string(),
+        },
+    ]
+}
+
+impl Templates {
+    /// Compiles the templates and renders every kind once, so that syntax
+    /// errors and unknown placeholders are caught before any event comes in.
+    pub fn load(path: Option<&str>) -> anyhow::Result<Self> {
+        let mut defaults = parse(DEFAULTS).context("Invalid default templates")?;
+        let mut overrides = match path {
+            None => BTreeMap::new(),
+            Some(path) => {
+                let source = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path))?;
+                parse(&source).with_context(|| format!("Invalid templates in {}", path))?
+            }
+        };
+
+        let mut env = minijinja::Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+
+        for kind in KINDS {
+            let default = defaults.remove(kind).unwrap_or_default();
+            let custom = overrides.remove(kind).unwrap_or_default();
+
+            for (part, source) in [
+                ("title", custom.title.or(default.title)),
+                ("body", custom.body.or(default.body)),
+            ] {
+                let name = format!("{}.{}", kind, part);
+                let source = source.with_context(|| format!("Missing {} template", name))?;
+                env.add_template_owned(name.clone(), source)
+                    .with_context(|| format!("Invalid {} template", name))?;
+            }
+        }
+
+        let templates = Self { env };
+        for sample in samples() {
+            templates.render(&sample)?;
+        }
+
+        Ok(templates)
+    }
+
+    pub fn render(&self, notification: &Notification) -> anyhow::Result<Rendered> {
+        let kind = notification.kind();
+        let mut value = serde_json::to_value(notification)?;
+        let context = value["data"].take();
+
+        let render = |part: &str| -> anyhow::Result<String> {
+            let name = format!("{}.{}", kind, part);
+            self.env
+                .get_template(&name)?
+                .render(&context)
+                .with_context(|| format!("Failed to render {} template", name))
+        };
+
+        Ok(Rendered {
+            title: render("title")?,
+            body: render("body")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::notifiers::testing::follow;
+
+    fn write(source: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "nost-templates-{:016x}.toml",
+            rand::random::<u64>()
+        ));
+        std::fs::write(&path, source).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    #[traced_test]
+    fn render_defaults() {
+        // arrange
+        let templates = Templates::load(None).unwrap();
+
+        // act
+        let res = templates.render(&Notification::Resubscribe {
+            username: "arinono".to_string(),
+            tier: SubTier::Tier1,
+            cumulative_months: 12,
+            streak_months: Some(3),
+            message: "Hello!".to_string(),
+        });
+
+        // assert
+        assert!(res.is_ok());
+        let rendered = res.unwrap();
+        assert_eq!(rendered.title, "New Resub".to_string());
+        assert_eq!(
+            rendered.body,
+            "arinono resubscribed with a tier1 sub for 12 months, 3 in a row!\nHello!".to_string()
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn render_recap_defaults() {
+        // arrange
+        let templates = Templates::load(None).unwrap();
+
+        // act
+        let res = templates.render(&Notification::Recap(Recap {
+            new_followers: 2,
+            subs: vec![
+                RecapTier {
+                    tier: "Tier1".to_string(),
+                    count: 3,
+                },
+                RecapTier {
+                    tier: "Prime".to_string(),
+                    count: 1,
+                },
+            ],
+            subgifts: 0,
+            bits: 0,
+            top_gifter: None,
+            top_cheerer: Some(RecapContributor {
+                name: "arinono".to_string(),
+                total: 100,
+            }),
+        }));
+
+        // assert
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap().body,
+            "New followers: 2\nNew subs: 3 tier1, 1 prime\nSub gifts: 0\nBits: 0\nTop gifter: -\nTop cheerer: arinono (100)".to_string()
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn load_overrides() {
+        // arrange
+        let path = write(
+            r#"
+[follow]
+body = "Bienvenue {{ username }} !"
+"#,
+        );
+        let templates = Templates::load(Some(&path)).unwrap();
+
+        // act
+        let res = templates.render(&follow());
+
+        // assert
+        assert!(res.is_ok());
+        let rendered = res.unwrap();
+        assert_eq!(rendered.title, "New Follower".to_string());
+        assert_eq!(rendered.body, "Bienvenue arinono !".to_string());
+    }
+
+    #[test]
+    #[traced_test]
+    fn load_unknown_placeholder() {
+        // arrange
+        let path = write(
+            r#"
+[follow]
+body = "{{ username }} gave {{ bits }} bits"
+"#,
+        );
+
+        // act
+        let res = Templates::load(Some(&path));
+
+        // assert
+        assert!(res.is_err());
+        let err = format!("{:#}", res.err().unwrap());
+        assert!(err.contains("follow.body"), "{}", err);
+    }
+
+    #[test]
+    #[traced_test]
+    fn load_syntax_error() {
+        // arrange
+        let path = write(
+            r#"
+[bits]
+title = "{{ username"
+"#,
+        );
+
+        // act
+        let res = Templates::load(Some(&path));
+
+        // assert
+        assert!(res.is_err());
+        let err = format!("{:#}", res.err().unwrap());
+        assert!(err.contains("bits.title"), "{}", err);
+    }
+
+    #[test]
+    #[traced_test]
+    fn load_unknown_kind() {
+        // arrange
+        let path = write(
+            r#"
+[host]
+title = "New Host"
+"#,
+        );
+
+        // act
+        let res = Templates::load(Some(&path));
+
+        // assert
+        assert!(res.is_err());
+        let err = format!("{:#}", res.err().unwrap());
+        assert!(err.contains("unknown notification kind `host`"), "{}", err);
+    }
+}
@@ -0,0 +1,39 @@
+//! Helpers shared by the notifier tests.
+
+use std::sync::{Arc, Mutex};
+
+use axum::Router;
+
+use super::{Envelope, Notification, Templates};
+
+/// What a stand-in received, in order.
+pub type Log<T> = Arc<Mutex<Vec<T>>>;
+
+/// Serves `app` on a free local port in place of a sink's API and returns
+/// its base URL, e.g. `http://127.0.0.1:4242`.
+pub async fn stand_in(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    url
+}
+
+pub fn templates() -> Arc<Templates> {
+    Arc::new(Templates::load(None).unwrap())
+}
+
+pub fn follow() -> Notification {
+    Notification::Follow {
+        username: "arinono".to_string(),
+    }
+}
+
+pub fn envelope(notification: Notification) -> Envelope {
+    Envelope {
+        id: "abc123".to_string(),
+        timestamp: "2025-02-10T20:00:00.000Z".to_string(),
+        user_id: None,
+        notification,
+    }
+}
@@ -0,0 +1,229 @@
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::env::Secret;
+
+use super::{check_response, http_client, Envelope, Notifier};
+
+const MESSAGE_ID: &str = "Nost-Message-Id";
+const MESSAGE_TIMESTAMP: &str = "Nost-Message-Timestamp";
+const MESSAGE_SIGNATURE: &str = "Nost-Message-Signature";
+
+/// Bumped on any breaking change to `WebhookPayload`.
+const SCHEMA_VERSION: u8 = 1;
+
+#[derive(Clone)]
+pub struct WebhookTarget {
+    pub url: String,
+    pub secret: Secret,
+}
+
+/// The body POSTed to the target, e.g.
+/// `{"version":1,"id":"…","timestamp":"…","user_id":"…","type":"follow","data":{"username":"…"}}`.
+/// `id` stays the same across retries so receivers can deduplicate, `user_id`
+/// is left out when nobody in particular triggered the event.
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload<'a> {
+    pub version: u8,
+    #[serde(flatten)]
+    pub envelope: &'a Envelope,
+}
+
+/// Forwards events to another service. Every request carries a
+/// `Nost-Message-Signature` header of `sha256=` followed by the lowercase hex
+/// HMAC-SHA256, keyed with the target secret, of
+/// `{Nost-Message-Id}.{Nost-Message-Timestamp}.{body}`. Receivers rebuild that
+/// string from the headers and the raw body, compare signatures in constant
+/// time and drop messages whose timestamp is too old.
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    target: WebhookTarget,
+}
+
+impl WebhookNotifier {
+    pub fn new(target: WebhookTarget) -> Self {
+        Self {
+            client: http_client(),
+            target,
+        }
+    }
+}
+
+fn signature(secret: &Secret, id: &str, timestamp: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.secret()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}.{}.{}", id, timestamp, body).as_bytes());
+
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    format!("sha256={}", hex)
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn target(&self) -> String {
+        self.target.url.clone()
+    }
+
+    async fn deliver(&self, envelope: &Envelope) -> anyhow::Result<()> {
+        let body = serde_json::to_string(&WebhookPayload {
+            version: SCHEMA_VERSION,
+            envelope,
+        })?;
+
+        let response = self
+            .client
+            .post(&self.target.url)
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .header(MESSAGE_ID, &envelope.id)
+            .header(MESSAGE_TIMESTAMP, &envelope.timestamp)
+            .header(
+                MESSAGE_SIGNATURE,
+                signature(
+                    &self.target.secret,
+                    &envelope.id,
+                    &envelope.timestamp,
+                    &body,
+                ),
+            )
+            .body(body)
+            .send()
+            .await?;
+
+        check_response(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{extract::State, http::HeaderMap, routing::post, Router};
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::notifiers::testing::{envelope, follow, stand_in, Log};
+
+    /// Stands in for a receiver: records the headers and raw body of every
+    /// request.
+    async fn receiver() -> (String, Log<(HeaderMap, String)>) {
+        let received: Log<(HeaderMap, String)> = Log::default();
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(
+                    |State(received): State<Log<(HeaderMap, String)>>,
+                     headers: HeaderMap,
+                     body: String| async move {
+                        received.lock().unwrap().push((headers, body));
+                    },
+                ),
+            )
+            .with_state(received.clone());
+
+        (format!("{}/hook", stand_in(app).await), received)
+    }
+
+    fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+        headers.get(name).unwrap().to_str().unwrap()
+    }
+
+    #[test]
+    #[traced_test]
+    fn signature_known_input() {
+        // arrange
+        let secret: Secret = "s3cr3t".parse().unwrap();
+
+        // act
+        let res = signature(
+            &secret,
+            "0123456789abcdef",
+            "2025-02-10T20:00:00.000Z",
+            r#"{"version":1}"#,
+        );
+
+        // assert
+        assert_eq!(
+            res,
+            "sha256=208258bfe01e2731d72a64fa9162c9de50ff58860d729fc025889166017dc6d3"
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn deliver_signed() {
+        // arrange
+        let (url, received) = receiver().await;
+        let webhook = WebhookNotifier::new(WebhookTarget {
+            url,
+            secret: "s3cr3t".parse().unwrap(),
+        });
+
+        // act
+        let res = webhook.deliver(&envelope(follow())).await;
+
+        // assert
+        assert!(res.is_ok());
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (headers, body) = &received[0];
+        assert_eq!(header(headers, "Nost-Message-Id"), "abc123");
+        assert_eq!(
+            header(headers, "Nost-Message-Timestamp"),
+            "2025-02-10T20:00:00.000Z"
+        );
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cr3t").unwrap();
+        mac.update(format!("abc123.2025-02-10T20:00:00.000Z.{}", body).as_bytes());
+        let expected: String = mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert_eq!(
+            header(headers, "Nost-Message-Signature"),
+            format!("sha256={}", expected)
+        );
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["type"], "follow");
+    }
+
+    #[test]
+    #[traced_test]
+    fn payload_schema() {
+        // arrange
+        let envelope = Envelope {
+            user_id: Some("1234".to_string()),
+            ..envelope(follow())
+        };
+
+        // act
+        let res = serde_json::to_value(WebhookPayload {
+            version: SCHEMA_VERSION,
+            envelope: &envelope,
+        })
+        .unwrap();
+
+        // assert
+        assert_eq!(
+            res,
+            serde_json::json!({
+                "version": 1,
+                "id": "abc123",
+                "timestamp": "2025-02-10T20:00:00.000Z",
+                "user_id": "1234",
+                "type": "follow",
+                "data": { "username": "arinono" },
+            })
+        );
+    }
+}
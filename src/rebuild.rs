@@ -0,0 +1,290 @@
+use chrono::{DateTime, Utc};
+use tables::{events::Event, streams::Stream, Orm, OrmError};
+
+use crate::{
+    database::Database,
+    twitch::projection::{self, Applied},
+};
+
+const BATCH_SIZE: u64 = 500;
+
+/// Tables derived from the journal, in an order that respects foreign keys.
+const DERIVED_TABLES: [&str; 7] = [
+    "latests",
+    "bits",
+    "subgifts",
+    "raids",
+    "resubs",
+    "redemptions",
+    "streams",
+];
+
+/// The `users` columns events set. Users themselves are kept, along with
+/// what the journal doesn't record: profiles, flags and deletions.
+const DERIVED_USER_COLUMNS: [&str; 4] = [
+    "follower_since",
+    "subscriber_since",
+    "subscription_tier",
+    "subgift_total",
+];
+
+/// How many journal entries a rebuild applied, had nothing to apply or
+/// could not apply.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub replayed: u64,
+    pub skipped: u64,
+    pub failed: u64,
+}
+
+fn timestamp(entry: &Event) -> DateTime<Utc> {
+    entry
+        .message_timestamp
+        .as_deref()
+        .and_then(tables::timestamp::parse)
+        .unwrap_or(entry.created_at)
+}
+
+/// Regenerates the tables derived from the journal (`latests`, `streams`,
+/// the per-event tables and the event columns of `users`) by replaying every
+/// journaled notification through the same logic as the eventsub handler.
+/// Per-event rows that are not backed by the journal (e.g. CSV imports) are
+/// dropped, so it only runs with `force`.
+pub async fn rebuild(database: &Database, force: bool) -> eyre::Result<Replay> {
+    if !force {
+        eyre::bail!(
+            "rebuild drops every row of {} and resets users' {}, then replays the event journal. \
+            Rows the journal doesn't back (e.g. CSV imports) are lost, run `rebuild --force` to proceed",
+            DERIVED_TABLES.join(", "),
+            DERIVED_USER_COLUMNS.join(", "),
+        );
+    }
+
+    let tx = database.transaction().await?;
+
+    tracing::warn!("Rebuilding derived tables from the event journal");
+
+    for table in DERIVED_TABLES {
+        Orm::<()>::execute(&tx, &format!("delete from {}", table), vec![]).await?;
+    }
+    let reset = DERIVED_USER_COLUMNS
+        .map(|column| format!("{} = null", column))
+        .join(", ");
+    Orm::<()>::execute(&tx, &format!("update users set {}", reset), vec![]).await?;
+    Orm::<()>::execute(
+        &tx,
+        "update events set user_id = null, stream_id = null",
+        vec![],
+    )
+    .await?;
+
+    let mut replay = Replay::default();
+    let mut cursor = 0;
+
+    loop {
+        let entries = Event::list_notifications_after(&tx, cursor, BATCH_SIZE).await?;
+
+        for entry in entries.iter() {
+            cursor = entry.id;
+
+            let raw = entry.raw.as_deref().unwrap_or_default();
+            let event = match twitch_api::eventsub::Event::parse(raw) {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::warn!(id = entry.id, "Skipping unparsable event: {}", e);
+                    replay.skipped += 1;
+                    continue;
+                }
+            };
+
+            // tagged like the eventsub handler does, before the event is applied
+            if let Some(stream) = Stream::active(&tx).await? {
+                Event::set_stream_id(&tx, entry.id, stream.id).await?;
+            }
+
+            // an entry that fails is undone on its own, as it was live
+            Orm::<()>::execute(&tx, "savepoint entry", vec![]).await?;
+
+            match projection::apply(&tx, &event, timestamp(entry)).await {
+                Ok(Applied::Nothing) => replay.skipped += 1,
+                Ok(Applied::Anonymous | Applied::Stream(_)) => replay.replayed += 1,
+                Ok(Applied::User(user_id)) => {
+                    Event::set_user_id(&tx, entry.id, user_id).await?;
+                    replay.replayed += 1;
+                }
+                // e.g. events of users deleted since
+                Err(OrmError::NoChange(reason)) => {
+                    tracing::warn!(id = entry.id, "Skipping event: {}", reason);
+                    Orm::<()>::execute(&tx, "rollback to entry", vec![]).await?;
+                    replay.skipped += 1;
+                }
+                // e.g. events that failed validation live as well
+                Err(e) => {
+                    tracing::error!(id = entry.id, "Skipping event that fails to apply: {}", e);
+                    Orm::<()>::execute(&tx, "rollback to entry", vec![]).await?;
+                    replay.failed += 1;
+                }
+            }
+
+            Orm::<()>::execute(&tx, "release entry", vec![]).await?;
+        }
+
+        if (entries.len() as u64) < BATCH_SIZE {
+            break;
+        }
+    }
+
+    tx.commit().await?;
+
+    if replay.failed > 0 {
+        tracing::warn!(
+            failed = replay.failed,
+            "Some journaled events failed to apply and were left out"
+        );
+    }
+    tracing::info!(
+        replayed = replay.replayed,
+        skipped = replay.skipped,
+        failed = replay.failed,
+        "Rebuilt derived tables from the event journal"
+    );
+
+    Ok(replay)
+}
+
+#[cfg(test)]
+mod tests {
+    use libsql::Connection;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::twitch::testing::{cheer, follow, parse};
+
+    /// Journals `raws` a second apart, applying each like the eventsub
+    /// handler does.
+    async fn journal(conn: &Connection, raws: &[String]) {
+        let start = tables::timestamp::parse("2025-02-10T20:00:00.000Z").unwrap();
+
+        for (i, raw) in raws.iter().enumerate() {
+            let at = start + chrono::Duration::seconds(i as i64);
+            let event = parse(raw);
+            Event::notification(
+                format!("message-{}", i),
+                event.subscription().unwrap().type_.to_string(),
+                event.subscription().unwrap().version.to_string(),
+                tables::timestamp::format(&at),
+                raw.clone(),
+            )
+            .create(conn)
+            .await
+            .unwrap();
+
+            let _ = projection::apply(conn, &event, at).await;
+        }
+    }
+
+    /// The derived tables, without the ids a rebuild renumbers.
+    async fn projections(conn: &Connection) -> Vec<String> {
+        let queries = [
+            "select twitch_id, display_name, follower_since, subscriber_since, subscription_tier, subgift_total
+                from users order by twitch_id",
+            "select u.twitch_id, b.number, b.message, b.created_at
+                from bits b left join users u on u.id = b.user_id order by b.created_at",
+            "select f.twitch_id, b.number, b.created_at
+                from latests l
+                left join users f on f.id = l.follower
+                left join bits b on b.id = l.bit",
+        ];
+
+        let mut rows = vec![];
+        for query in queries {
+            let mut res = conn.query(query, ()).await.unwrap();
+            while let Some(row) = res.next().await.unwrap() {
+                let values: Vec<String> = (0..row.column_count())
+                    .map(|i| format!("{:?}", row.get_value(i).unwrap()))
+                    .collect();
+                rows.push(values.join(", "));
+            }
+        }
+
+        rows
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn rebuild_requires_force() {
+        // arrange
+        let database = Database::scratch().await;
+        let conn = database.conn().unwrap();
+        journal(&conn, &[follow(1, "arinono")]).await;
+
+        // act
+        let res = rebuild(&database, false).await;
+
+        // assert
+        assert!(res.is_err());
+        assert!(!projections(&conn).await.is_empty());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn rebuild_skips_failing_entries() {
+        // arrange
+        let database = Database::scratch().await;
+        let conn = database.conn().unwrap();
+        journal(
+            &conn,
+            &[
+                follow(1, "arinono"),
+                cheer(None, "nobody", 500),
+                cheer(Some(1), "arinono", 100),
+            ],
+        )
+        .await;
+        let live = projections(&conn).await;
+
+        // act
+        let res = rebuild(&database, true).await;
+
+        // assert
+        assert_eq!(
+            res.unwrap(),
+            Replay {
+                replayed: 2,
+                skipped: 0,
+                failed: 1,
+            }
+        );
+        assert_eq!(projections(&conn).await, live);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn rebuild_twice() {
+        // arrange
+        let database = Database::scratch().await;
+        let conn = database.conn().unwrap();
+        journal(
+            &conn,
+            &[
+                follow(1, "arinono"),
+                follow(2, "hydrate"),
+                cheer(Some(2), "hydrate", 100),
+                cheer(Some(1), "arinono", 250),
+            ],
+        )
+        .await;
+        let live = projections(&conn).await;
+
+        // act
+        let first = rebuild(&database, true).await.unwrap();
+        let once = projections(&conn).await;
+        let second = rebuild(&database, true).await.unwrap();
+        let twice = projections(&conn).await;
+
+        // assert
+        assert_eq!(first, second);
+        assert_eq!(once, live);
+        assert_eq!(twice, live);
+    }
+}
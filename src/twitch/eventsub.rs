@@ -4,24 +4,36 @@ use axum::{
     http::{self, StatusCode},
     response::IntoResponse,
 };
-use chrono::Utc;
 use twitch_api::eventsub::{
     channel::{
-        ChannelCheerV1Payload, ChannelFollowV2Payload, ChannelSubscribeV1Payload,
-        ChannelSubscriptionEndV1Payload, ChannelSubscriptionGiftV1Payload,
+        ChannelCheerV1Payload, ChannelFollowV2Payload,
+        ChannelPointsCustomRewardRedemptionAddV1Payload,
+        ChannelPointsCustomRewardRedemptionUpdateV1Payload, ChannelRaidV1Payload,
+        ChannelSubscribeV1Payload, ChannelSubscriptionEndV1Payload,
+        ChannelSubscriptionGiftV1Payload, ChannelSubscriptionMessageV1Payload,
     },
+    stream::{StreamOfflineV1Payload, StreamOnlineV1Payload},
     Event,
 };
-use twitch_types::DisplayName;
 
 use crate::{
-    discord::DiscordNotifier,
+    bus::{EventKind, LiveEvent},
+    database::Database,
     models::{self, sub_tier::SubTier},
+    notifiers::{Notification, Notifiers, Rules},
     AppState,
 };
-use tables::{Orm, OrmBase, TwitchId};
+use tables::OrmError;
+
+use super::{
+    follows::Verdict,
+    projection::{self, Applied},
+};
 
 const TWI_MSG_ID: &str = "Twitch-Eventsub-Message-Id";
+const TWI_MSG_TIMESTAMP: &str = "Twitch-Eventsub-Message-Timestamp";
+const TWI_SUB_TYPE: &str = "Twitch-Eventsub-Subscription-Type";
+const TWI_SUB_VERSION: &str = "Twitch-Eventsub-Subscription-Version";
 
 const MAX_ALLOWED_RESPONSE_SIZE: u64 = 64 * 1024;
 
@@ -86,12 +98,48 @@ pub async fn eventsub(
         return (StatusCode::OK, "".to_string());
     }
 
+    let live_event = EventKind::from_event(&event).map(LiveEvent::now);
+    // the time the journal keeps, so a rebuild reproduces the same rows
+    let at = tables::timestamp::parse(&header(&request, TWI_MSG_TIMESTAMP))
+        .unwrap_or_else(chrono::Utc::now);
+
+    let mut entry = tables::events::Event::notification(
+        header(&request, TWI_MSG_ID),
+        header(&request, TWI_SUB_TYPE),
+        header(&request, TWI_SUB_VERSION),
+        header(&request, TWI_MSG_TIMESTAMP),
+        String::from_utf8_lossy(request.body()).to_string(),
+    );
+    if let Some(live_event) = &live_event {
+        match serde_json::to_string(live_event) {
+            Ok(payload) => entry = entry.live(live_event.name().to_string(), payload),
+            Err(e) => tracing::error!("Failed to serialize live event: {}", e),
+        }
+    }
+
+    // the journal is written before any derived state so it can be replayed
+    let journal_id = match journal(&app_state, entry).await {
+        Ok(id) => Some(id),
+        Err(OrmError::NoChange(_)) => {
+            tracing::info!("got already journaled event");
+            return (StatusCode::OK, "".to_string());
+        }
+        Err(e) => {
+            tracing::error!("Failed to journal event: {}", e);
+            None
+        }
+    };
+
+    if let Some(mut live_event) = live_event {
+        live_event.id = journal_id;
+        app_state.bus.publish(live_event);
+    }
+
     use twitch_api::eventsub::{Message as M, Payload as P};
 
-    let discord =
-        DiscordNotifier::new(app_state.env.discord_webhook_url.secret_str().to_owned()).await;
+    let notifiers = app_state.notifiers.clone();
 
-    match event {
+    match &event {
         Event::ChannelFollowV2(P {
             message:
                 M::Notification(ChannelFollowV2Payload {
@@ -99,46 +147,8 @@ pub async fn eventsub(
                 }),
             ..
         }) => {
+            // notified once applied, see `on_follow`
             tracing::info!("got follow event from {} ({})", user_name, user_id);
-
-            let database = app_state.database.clone();
-            tokio::spawn(async move {
-                discord.new_follower(&user_name.to_string()).await;
-                let db = database.db().unwrap();
-                let conn = database.conn().unwrap();
-
-                let twitch_id: TwitchId = user_id.into();
-                let user = tables::user::User::get_by_twitch_id(&conn, twitch_id.0)
-                    .await
-                    .expect("Failure to retrieve user");
-
-                match user {
-                    None => {
-                        let new_user =
-                            tables::user::User::builder(user_name.to_string(), twitch_id.0)
-                                .follow(Orm::<()>::now_utc())
-                                .build();
-
-                        new_user.create(&conn).await.expect("Failed to create user");
-
-                        if !app_state.env.dev_mode {
-                            db.sync().await.expect("Failed to sync replica");
-                        }
-                    }
-                    Some(mut user) => {
-                        user.follower_since = Some(Utc::now().to_rfc3339());
-                        user.display_name = user_name.to_string();
-
-                        user.update(&conn).await.expect("Failed to update user");
-
-                        if !app_state.env.dev_mode {
-                            db.sync().await.expect("Failed to sync replica");
-                        }
-                    }
-                }
-            });
-
-            return ack;
         }
         Event::ChannelSubscribeV1(P {
             message:
@@ -150,7 +160,7 @@ pub async fn eventsub(
                 }),
             ..
         }) => {
-            let tier = models::sub_tier::SubTier::from(tier);
+            let tier = models::sub_tier::SubTier::from(tier.clone());
             tracing::info!(
                 "got sub event from {} ({}) tier {}",
                 user_name,
@@ -158,46 +168,60 @@ pub async fn eventsub(
                 tier,
             );
 
-            let database = app_state.database.clone();
-            tokio::spawn(async move {
-                discord.new_subscriber(&user_name.to_string(), &tier).await;
-                let db = database.db().unwrap();
-                let conn = database.conn().unwrap();
-
-                let twitch_id: TwitchId = user_id.into();
-                let user = tables::user::User::get_by_twitch_id(&conn, twitch_id.0)
-                    .await
-                    .expect("Failure to retrieve user");
-
-                match user {
-                    None => {
-                        let new_user =
-                            tables::user::User::builder(user_name.to_string(), twitch_id.0)
-                                .subscribe(Orm::<()>::now_utc())
-                                .tier(tier.to_string())
-                                .build();
-
-                        new_user.create(&conn).await.expect("Failed to create user");
-
-                        if !app_state.env.dev_mode {
-                            db.sync().await.expect("Failed to sync replica");
-                        }
-                    }
-                    Some(mut user) => {
-                        user.subscriber_since = Some(Orm::<()>::now_utc());
-                        user.subscription_tier = Some(tier.to_string());
-                        user.display_name = user_name.to_string();
-
-                        user.update(&conn).await.expect("Failed to update user");
-
-                        if !app_state.env.dev_mode {
-                            db.sync().await.expect("Failed to sync replica");
-                        }
-                    }
-                }
-            });
+            notify(
+                &app_state.rules,
+                &notifiers,
+                Some(user_id.as_str()),
+                Notification::Subscribe {
+                    username: user_name.to_string(),
+                    tier,
+                },
+            )
+            .await;
+        }
+        Event::ChannelSubscriptionMessageV1(P {
+            message:
+                M::Notification(ChannelSubscriptionMessageV1Payload {
+                    tier,
+                    user_id,
+                    user_name,
+                    cumulative_months,
+                    streak_months,
+                    message,
+                    ..
+                }),
+            ..
+        }) => {
+            let tier = SubTier::from(tier.clone());
+            let cumulative_months = if *cumulative_months > 0 {
+                *cumulative_months as usize
+            } else {
+                0
+            };
+            let streak_months = streak_months.map(|v| if v > 0 { v as usize } else { 0 });
+
+            tracing::info!(
+                "got resub event from {} ({}) tier {} months {} (streak: {:?})",
+                user_name,
+                user_id,
+                tier,
+                cumulative_months,
+                streak_months,
+            );
 
-            return ack;
+            notify(
+                &app_state.rules,
+                &notifiers,
+                Some(user_id.as_str()),
+                Notification::Resubscribe {
+                    username: user_name.to_string(),
+                    tier,
+                    cumulative_months,
+                    streak_months,
+                    message: message.text.clone(),
+                },
+            )
+            .await;
         }
         Event::ChannelSubscriptionEndV1(P {
             message:
@@ -207,44 +231,6 @@ pub async fn eventsub(
             ..
         }) => {
             tracing::info!("got sub end event from {} ({})", user_name, user_id);
-
-            let database = app_state.database.clone();
-            tokio::spawn(async move {
-                let db = database.db().unwrap();
-                let conn = database.conn().unwrap();
-
-                let twitch_id: TwitchId = user_id.into();
-                let user = tables::user::User::get_by_twitch_id(&conn, twitch_id.0)
-                    .await
-                    .expect("Failure to retrieve user");
-
-                match user {
-                    None => {
-                        tracing::warn!("got sub end event for unknown user");
-                        let new_user =
-                            tables::user::User::builder(user_name.to_string(), twitch_id.0).build();
-
-                        new_user.create(&conn).await.expect("Failed to create user");
-
-                        if !app_state.env.dev_mode {
-                            db.sync().await.expect("Failed to sync replica");
-                        }
-                    }
-                    Some(mut user) => {
-                        user.subscriber_since = None;
-                        user.subscription_tier = None;
-                        user.display_name = user_name.to_string();
-
-                        user.update(&conn).await.expect("Failed to update user");
-
-                        if !app_state.env.dev_mode {
-                            db.sync().await.expect("Failed to sync replica");
-                        }
-                    }
-                }
-            });
-
-            return ack;
         }
         Event::ChannelSubscriptionGiftV1(P {
             message:
@@ -253,21 +239,15 @@ pub async fn eventsub(
                     is_anonymous,
                     cumulative_total,
                     total,
-                    user_id: twitch_id,
+                    user_id,
                     user_name,
                     ..
                 }),
             ..
         }) => {
-            let username = match is_anonymous {
-                true => "Anonymous".to_string(),
-                false => user_name
-                    .clone()
-                    .unwrap_or(DisplayName::from("Anonymous"))
-                    .to_string(),
-            };
-            let tier = SubTier::from(tier);
-            let total = if total > 0 { total as usize } else { 0 };
+            let username = projection::username(*is_anonymous, user_name);
+            let tier = SubTier::from(tier.clone());
+            let total = if *total > 0 { *total as usize } else { 0 };
             let cumulative_total = cumulative_total.map(|v| v as usize);
 
             tracing::info!(
@@ -277,98 +257,22 @@ pub async fn eventsub(
                 total,
                 cumulative_total,
             );
-            discord.subgift(&username, total, &tier).await;
-
-            let database = app_state.database.clone();
-            tokio::spawn(async move {
-                let db = database.db().unwrap();
-                let conn = database.conn().unwrap();
-
-                let user = match twitch_id.clone() {
-                    Some(id) => {
-                        let twitch_id: TwitchId = id.into();
-                        tables::user::User::get_by_twitch_id(&conn, twitch_id.0)
-                            .await
-                            .expect("Failure to retrieve user")
-                    }
-                    None => None,
-                };
-
-                if is_anonymous {
-                    let subgift =
-                        tables::subgifts::Subgift::from_anonymous(total as u16, tier.to_string());
-
-                    subgift
-                        .create(&conn)
-                        .await
-                        .expect("Failed to create subgift");
-
-                    if !app_state.env.dev_mode {
-                        db.sync().await.expect("Failed to sync replica");
-                    }
-
-                    return;
-                }
-
-                match user {
-                    None => {
-                        let twitch_id: TwitchId = twitch_id
-                            .clone()
-                            .expect("a twitch_id for non anonymous user")
-                            .into();
-                        let new_user = tables::user::User::from(username, twitch_id.0);
-                        let user_id = new_user.create(&conn).await.expect("Failed to create user");
-
-                        let subgift = tables::subgifts::Subgift::from(
-                            user_id,
-                            total as u16,
-                            tier.to_string(),
-                        );
-
-                        subgift
-                            .create(&conn)
-                            .await
-                            .expect("Failed to create subgift");
-
-                        if !app_state.env.dev_mode {
-                            db.sync().await.expect("Failed to sync replica");
-                        }
-                    }
-                    Some(user) => {
-                        let mut update_user = user.clone();
-                        if let Some(user_name) = user_name {
-                            update_user.display_name = user_name.to_string();
-                        }
-
-                        update_user
-                            .update(&conn)
-                            .await
-                            .expect("Failed to update user");
-
-                        let subgift = tables::subgifts::Subgift::from(
-                            user.id,
-                            total as u16,
-                            tier.to_string(),
-                        );
-
-                        subgift
-                            .create(&conn)
-                            .await
-                            .expect("Failed to create subgift");
-
-                        if !app_state.env.dev_mode {
-                            db.sync().await.expect("Failed to sync replica");
-                        }
-                    }
-                }
-            });
-
-            return ack;
+            notify(
+                &app_state.rules,
+                &notifiers,
+                user_id.as_ref().map(|id| id.as_str()),
+                Notification::Subgift {
+                    username,
+                    total,
+                    tier,
+                },
+            )
+            .await;
         }
         Event::ChannelCheerV1(P {
             message:
                 M::Notification(ChannelCheerV1Payload {
-                    user_id: twitch_id,
+                    user_id,
                     user_name,
                     bits,
                     message,
@@ -377,13 +281,8 @@ pub async fn eventsub(
                 }),
             ..
         }) => {
-            let username = match is_anonymous {
-                true => "Anonymous".to_string(),
-                false => user_name
-                    .unwrap_or(DisplayName::from("Anonymous"))
-                    .to_string(),
-            };
-            let number = if bits > 0 { bits as usize } else { 0 };
+            let username = projection::username(*is_anonymous, user_name);
+            let number = if *bits > 0 { *bits as usize } else { 0 };
 
             tracing::info!(
                 "got bits event from {} bits {} message {}",
@@ -391,69 +290,350 @@ pub async fn eventsub(
                 number,
                 message,
             );
-            discord.bits(&username, number, &message).await;
-
-            let database = app_state.database.clone();
-            tokio::spawn(async move {
-                let db = database.db().unwrap();
-                let conn = database.conn().unwrap();
+            notify(
+                &app_state.rules,
+                &notifiers,
+                user_id.as_ref().map(|id| id.as_str()),
+                Notification::Bits {
+                    username,
+                    bits: number,
+                    message: message.clone(),
+                },
+            )
+            .await;
+        }
+        Event::ChannelPointsCustomRewardRedemptionAddV1(P {
+            message:
+                M::Notification(ChannelPointsCustomRewardRedemptionAddV1Payload {
+                    user_id,
+                    user_name,
+                    reward,
+                    user_input,
+                    ..
+                }),
+            ..
+        }) => {
+            let cost = if reward.cost > 0 {
+                reward.cost as usize
+            } else {
+                0
+            };
 
-                let user = match twitch_id.clone() {
-                    Some(id) => {
-                        let twitch_id: TwitchId = id.into();
-                        tables::user::User::get_by_twitch_id(&conn, twitch_id.0)
-                            .await
-                            .expect("Failure to retrieve user")
-                    }
-                    None => None,
-                };
+            tracing::info!(
+                "got redemption event from {} ({}) reward {} ({}) cost {}",
+                user_name,
+                user_id,
+                reward.title,
+                reward.id,
+                cost,
+            );
 
-                if is_anonymous {
-                    let bits = tables::bits::Bit::from_anonymous(number as u32, Some(message));
+            notify(
+                &app_state.rules,
+                &notifiers,
+                Some(user_id.as_str()),
+                Notification::Redemption {
+                    username: user_name.to_string(),
+                    reward_id: reward.id.to_string(),
+                    title: reward.title.clone(),
+                    cost,
+                    input: user_input.clone(),
+                },
+            )
+            .await;
+        }
+        Event::ChannelPointsCustomRewardRedemptionUpdateV1(P {
+            message:
+                M::Notification(ChannelPointsCustomRewardRedemptionUpdateV1Payload {
+                    id,
+                    user_name,
+                    reward,
+                    status,
+                    ..
+                }),
+            ..
+        }) => {
+            tracing::info!(
+                "got redemption update event from {} reward {} ({}) status {}",
+                user_name,
+                reward.title,
+                id,
+                projection::redemption_status(status),
+            );
+        }
+        Event::ChannelRaidV1(P {
+            message:
+                M::Notification(ChannelRaidV1Payload {
+                    from_broadcaster_user_id,
+                    from_broadcaster_user_name,
+                    viewers,
+                    ..
+                }),
+            ..
+        }) => {
+            let viewers = if *viewers > 0 { *viewers as usize } else { 0 };
 
-                    bits.create(&conn).await.expect("Failed to create bits");
+            tracing::info!(
+                "got raid event from {} ({}) with {} viewers",
+                from_broadcaster_user_name,
+                from_broadcaster_user_id,
+                viewers,
+            );
 
-                    if !app_state.env.dev_mode {
-                        db.sync().await.expect("Failed to sync replica");
-                    }
+            notify(
+                &app_state.rules,
+                &notifiers,
+                Some(from_broadcaster_user_id.as_str()),
+                Notification::Raid {
+                    username: from_broadcaster_user_name.to_string(),
+                    viewers,
+                },
+            )
+            .await;
+        }
+        Event::StreamOnlineV1(P {
+            message: M::Notification(StreamOnlineV1Payload { id, started_at, .. }),
+            ..
+        }) => {
+            tracing::info!(
+                "got stream online event for stream {} at {}",
+                id,
+                started_at
+            );
+        }
+        Event::StreamOfflineV1(P {
+            message: M::Notification(StreamOfflineV1Payload { .. }),
+            ..
+        }) => {
+            tracing::info!("got stream offline event");
+        }
+        _ => {}
+    }
 
-                    return;
+    let database = app_state.database.clone();
+    let dev_mode = app_state.env.dev_mode;
+    let notifiers = app_state.notifiers.clone();
+    let rules = app_state.rules.clone();
+    tokio::spawn(async move {
+        let db = database.db().unwrap();
+        let conn = database.conn().unwrap();
+
+        match apply(&database, &event, at).await {
+            Ok(Applied::Nothing) => return,
+            Ok(Applied::Anonymous) => {}
+            Ok(Applied::Stream(stream_id)) => {
+                if let Event::StreamOfflineV1(_) = &event {
+                    post_recap(&conn, stream_id, &rules, &notifiers).await;
                 }
+            }
+            Ok(Applied::User(user_id)) => {
+                link_journal_user(&conn, journal_id, user_id).await;
+                let user = app_state.profiles.enrich(&conn, user_id).await;
+                if let Event::ChannelFollowV2(P {
+                    message: M::Notification(payload),
+                    ..
+                }) = &event
+                {
+                    on_follow(&app_state, &conn, payload, user.as_ref()).await;
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to apply event: {}", e);
+                if let Event::ChannelFollowV2(P {
+                    message: M::Notification(payload),
+                    ..
+                }) = &event
+                {
+                    on_follow(&app_state, &conn, payload, None).await;
+                }
+                return;
+            }
+        }
+
+        if !dev_mode {
+            db.sync().await.expect("Failed to sync replica");
+        }
+    });
+
+    ack
+}
 
-                match user {
-                    None => {
-                        let twitch_id: TwitchId = twitch_id
-                            .clone()
-                            .expect("a twitch_id for non anonymous user")
-                            .into();
-                        let new_user = tables::user::User::from(username, twitch_id.0);
+/// Applies the event in a transaction, so a failure half-way (e.g. once the
+/// gifter is created but before the gift is) leaves nothing behind.
+async fn apply(
+    database: &Database,
+    event: &Event,
+    at: chrono::DateTime<chrono::Utc>,
+) -> Result<Applied, OrmError> {
+    let tx = database.transaction().await?;
+
+    match projection::apply(&tx, event, at).await {
+        Ok(applied) => {
+            tx.commit().await?;
+            Ok(applied)
+        }
+        Err(e) => {
+            if let Err(rollback) = tx.rollback().await {
+                tracing::error!("Failed to roll back event: {}", rollback);
+            }
+            Err(e)
+        }
+    }
+}
 
-                        let new_user_id =
-                            new_user.create(&conn).await.expect("Failed to create user");
+fn header(request: &http::Request<&[u8]>, name: &str) -> String {
+    request
+        .headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
 
-                        let bits =
-                            tables::bits::Bit::from(new_user_id, number as u32, Some(message));
+async fn journal(app_state: &AppState, mut entry: tables::events::Event) -> Result<u64, OrmError> {
+    let conn = app_state.database.conn()?;
 
-                        bits.create(&conn).await.expect("Failed to create bits");
+    entry.stream_id = tables::streams::Stream::active(&conn)
+        .await?
+        .map(|stream| stream.id);
 
-                        if !app_state.env.dev_mode {
-                            db.sync().await.expect("Failed to sync replica");
-                        }
-                    }
-                    Some(user) => {
-                        let bits = tables::bits::Bit::from(user.id, number as u32, Some(message));
+    entry.create(&conn).await
+}
 
-                        bits.create(&conn).await.expect("Failed to create bits");
+async fn link_journal_user(conn: &libsql::Connection, journal_id: Option<u64>, user_id: u64) {
+    if let Some(id) = journal_id {
+        if let Err(e) = tables::events::Event::set_user_id(conn, id, user_id).await {
+            tracing::error!("Failed to link user to journaled event: {}", e);
+        }
+    }
+}
+
+/// Hands the notification to the notifiers unless the rules filter it out.
+/// `user_id` is the Twitch id of whoever triggered the event, if known.
+pub async fn notify(
+    rules: &Rules,
+    notifiers: &Notifiers,
+    user_id: Option<&str>,
+    notification: Notification,
+) {
+    if !rules.allows(&notification, user_id) {
+        tracing::info!("{} notification filtered out by rules", notification.kind());
+        return;
+    }
+
+    notifiers.notify(user_id, notification).await;
+}
+
+/// Runs the follow through the follow-bot wave detection, which needs the
+/// account age. `user` is the `users` row, if the follow was applied,
+/// otherwise the age is looked up on Helix.
+async fn on_follow(
+    app_state: &AppState,
+    conn: &libsql::Connection,
+    payload: &ChannelFollowV2Payload,
+    user: Option<&tables::user::User>,
+) {
+    let followed_at =
+        tables::timestamp::parse(payload.followed_at.as_str()).unwrap_or_else(chrono::Utc::now);
+    let account_created_at = match user {
+        Some(user) => user.account_created_at,
+        None => app_state
+            .profiles
+            .fetch(&payload.user_id)
+            .await
+            .and_then(|helix_user| tables::timestamp::parse(helix_user.created_at.as_str())),
+    };
 
-                        if !app_state.env.dev_mode {
-                            db.sync().await.expect("Failed to sync replica");
-                        }
-                    }
+    match app_state.follows.observe(followed_at, account_created_at) {
+        Verdict::Notify => {
+            notify(
+                &app_state.rules,
+                &app_state.notifiers,
+                Some(payload.user_id.as_str()),
+                Notification::Follow {
+                    username: payload.user_name.to_string(),
+                },
+            )
+            .await
+        }
+        Verdict::Suppress { suspect } => {
+            tracing::info!(
+                "suppressed follow from {} ({}), suspect: {}",
+                payload.user_name,
+                payload.user_id,
+                suspect
+            );
+            if let (true, Some(user)) = (suspect, user) {
+                if let Err(e) = tables::user::User::flag_suspected(conn, user.id).await {
+                    tracing::error!("Failed to flag user {} as suspect: {}", user.id, e);
                 }
-            });
+            }
         }
-        _ => {}
     }
+}
+
+async fn post_recap(
+    conn: &libsql::Connection,
+    stream_id: u64,
+    rules: &Rules,
+    notifiers: &Notifiers,
+) {
+    let stream = match tables::streams::Stream::get(conn, stream_id).await {
+        Ok(Some(stream)) => stream,
+        Ok(None) => {
+            tracing::warn!("Stream {} vanished before its recap", stream_id);
+            return;
+        }
+        Err(e) => {
+            tracing::error!("Failed to get stream for recap: {}", e);
+            return;
+        }
+    };
+
+    match tables::recaps::Recap::for_stream(conn, &stream).await {
+        Ok(recap) => notify(rules, notifiers, None, Notification::Recap(recap)).await,
+        Err(e) => tracing::error!("Failed to compute stream recap: {}", e),
+    }
+}
 
-    (StatusCode::OK, "EventSub".to_string())
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tables::user::User;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::twitch::testing::{follow, parse};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    #[traced_test]
+    async fn apply_concurrently() {
+        // arrange
+        let database = Arc::new(Database::scratch().await);
+        let at = chrono::Utc::now();
+
+        // act
+        let tasks: Vec<_> = (1..=8)
+            .map(|twitch_id| {
+                let database = database.clone();
+                tokio::spawn(async move {
+                    let event = parse(&follow(twitch_id, &format!("follower{}", twitch_id)));
+                    apply(&database, &event, at).await
+                })
+            })
+            .collect();
+        let results = futures::future::join_all(tasks).await;
+
+        // assert
+        for res in results {
+            assert!(matches!(res.unwrap(), Ok(Applied::User(_))));
+        }
+        let conn = database.conn().unwrap();
+        for twitch_id in 1..=8 {
+            let user = User::get_by_twitch_id(&conn, twitch_id).await.unwrap();
+            assert!(user.unwrap().follower_since.is_some());
+        }
+        assert!(conn.is_autocommit());
+    }
 }
@@ -0,0 +1,268 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use tables::timestamp;
+
+use crate::notifiers::{Notification, Notifiers, Rules};
+
+use super::eventsub::notify;
+
+/// Follows are counted over a sliding window of `WINDOW_SECS`, keyed on the
+/// time Twitch says they happened.
+const WINDOW_SECS: i64 = 60;
+/// A wave starts when either count reaches its limit within the window.
+const MAX_FOLLOWS: usize = 20;
+const MAX_YOUNG_FOLLOWS: usize = 8;
+/// Accounts created less than this many days before following are young.
+const YOUNG_ACCOUNT_DAYS: i64 = 7;
+const SETTLE: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Notify,
+    /// Part of a wave: no notification, the user gets flagged when `suspect`.
+    Suppress {
+        suspect: bool,
+    },
+}
+
+#[derive(Debug)]
+struct Wave {
+    started_at: DateTime<Utc>,
+    last_at: DateTime<Utc>,
+    follows: usize,
+    suspects: usize,
+}
+
+#[derive(Debug, Default)]
+struct WatchState {
+    /// Follow time and whether the account was young, oldest first.
+    window: VecDeque<(DateTime<Utc>, bool)>,
+    wave: Option<Wave>,
+}
+
+impl WatchState {
+    fn evict(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - TimeDelta::seconds(WINDOW_SECS);
+        while self.window.front().is_some_and(|(at, _)| *at <= cutoff) {
+            self.window.pop_front();
+        }
+    }
+
+    fn counts(&self) -> (usize, usize) {
+        let young = self.window.iter().filter(|(_, young)| *young).count();
+
+        (self.window.len(), young)
+    }
+}
+
+/// Detects follow-bot waves. While one is going on follows are not notified
+/// one by one, a single `Notification::FollowWave` is sent once it is over.
+#[derive(Clone, Default)]
+pub struct FollowWatch {
+    state: Arc<Mutex<WatchState>>,
+}
+
+impl FollowWatch {
+    /// `account_created_at` is unknown when the Helix lookup failed, such
+    /// follows count towards the wave but are never flagged.
+    pub fn observe(
+        &self,
+        followed_at: DateTime<Utc>,
+        account_created_at: Option<DateTime<Utc>>,
+    ) -> Verdict {
+        let young = account_created_at.is_some_and(|created_at| {
+            followed_at - created_at < TimeDelta::days(YOUNG_ACCOUNT_DAYS)
+        });
+
+        let mut state = self.state.lock().unwrap();
+        state.window.push_back((followed_at, young));
+        let latest = state
+            .window
+            .iter()
+            .map(|(at, _)| *at)
+            .max()
+            .unwrap_or(followed_at);
+        state.evict(latest);
+
+        let (follows, young_follows) = state.counts();
+        if state.wave.is_none() && (follows >= MAX_FOLLOWS || young_follows >= MAX_YOUNG_FOLLOWS) {
+            tracing::warn!(
+                "Follow-bot wave detected: {} follows, {} from young accounts, in {}s",
+                follows,
+                young_follows,
+                WINDOW_SECS
+            );
+            state.wave = Some(Wave {
+                started_at: followed_at,
+                last_at: followed_at,
+                follows: 0,
+                suspects: 0,
+            });
+        }
+
+        match state.wave.as_mut() {
+            None => Verdict::Notify,
+            Some(wave) => {
+                wave.follows += 1;
+                wave.last_at = wave.last_at.max(followed_at);
+                if young {
+                    wave.suspects += 1;
+                }
+                Verdict::Suppress { suspect: young }
+            }
+        }
+    }
+
+    /// Ends the wave once both counts are back under half their limit and
+    /// returns its summary.
+    pub fn settle(&self, now: DateTime<Utc>) -> Option<Notification> {
+        let mut state = self.state.lock().unwrap();
+        state.evict(now);
+
+        let (follows, young_follows) = state.counts();
+        if follows * 2 >= MAX_FOLLOWS || young_follows * 2 >= MAX_YOUNG_FOLLOWS {
+            return None;
+        }
+
+        state.wave.take().map(|wave| {
+            tracing::info!(
+                "Follow-bot wave over: {} follows, {} suspects",
+                wave.follows,
+                wave.suspects
+            );
+            Notification::FollowWave {
+                follows: wave.follows,
+                suspects: wave.suspects,
+                started_at: timestamp::format(&wave.started_at),
+                ended_at: timestamp::format(&wave.last_at),
+            }
+        })
+    }
+}
+
+/// Sends the summary of each wave once it has settled.
+pub async fn run(
+    watch: FollowWatch,
+    rules: Rules,
+    notifiers: Notifiers,
+) -> Result<(), eyre::Report> {
+    loop {
+        tokio::time::sleep(SETTLE).await;
+
+        if let Some(summary) = watch.settle(Utc::now()) {
+            notify(&rules, &notifiers, None, summary).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-02-10T20:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + TimeDelta::seconds(secs)
+    }
+
+    fn young() -> Option<DateTime<Utc>> {
+        Some(at(0) - TimeDelta::days(1))
+    }
+
+    fn old() -> Option<DateTime<Utc>> {
+        Some(at(0) - TimeDelta::days(365))
+    }
+
+    #[test]
+    #[traced_test]
+    fn observe_steady_follows() {
+        // arrange
+        let watch = FollowWatch::default();
+
+        // act
+        let verdicts: Vec<Verdict> = (0..100)
+            .map(|i| watch.observe(at(i * 10), young()))
+            .collect();
+
+        // assert
+        assert!(verdicts.iter().all(|v| *v == Verdict::Notify));
+    }
+
+    #[test]
+    #[traced_test]
+    fn observe_young_accounts_wave() {
+        // arrange
+        let watch = FollowWatch::default();
+        for i in 0..(MAX_YOUNG_FOLLOWS as i64 - 1) {
+            assert_eq!(watch.observe(at(i), young()), Verdict::Notify);
+        }
+
+        // act
+        let tripping = watch.observe(at(10), young());
+        let old_account = watch.observe(at(11), old());
+        let unknown_age = watch.observe(at(12), None);
+
+        // assert
+        assert_eq!(tripping, Verdict::Suppress { suspect: true });
+        assert_eq!(old_account, Verdict::Suppress { suspect: false });
+        assert_eq!(unknown_age, Verdict::Suppress { suspect: false });
+    }
+
+    #[test]
+    #[traced_test]
+    fn observe_follow_rate_wave() {
+        // arrange
+        let watch = FollowWatch::default();
+        for i in 0..(MAX_FOLLOWS as i64 - 1) {
+            assert_eq!(watch.observe(at(i), old()), Verdict::Notify);
+        }
+
+        // act
+        let res = watch.observe(at(30), old());
+
+        // assert
+        assert_eq!(res, Verdict::Suppress { suspect: false });
+    }
+
+    #[test]
+    #[traced_test]
+    fn settle() {
+        // arrange
+        let watch = FollowWatch::default();
+        for i in 0..30 {
+            watch.observe(at(i), young());
+        }
+
+        // act
+        let during = watch.settle(at(45));
+        let after = watch.settle(at(120));
+        let again = watch.settle(at(130));
+
+        // assert
+        assert!(during.is_none());
+        match after {
+            Some(Notification::FollowWave {
+                follows,
+                suspects,
+                started_at,
+                ended_at,
+            }) => {
+                assert_eq!(follows, 30 - (MAX_YOUNG_FOLLOWS - 1));
+                assert_eq!(suspects, follows);
+                assert_eq!(started_at, "2025-02-10T20:00:07.000Z".to_string());
+                assert_eq!(ended_at, "2025-02-10T20:00:29.000Z".to_string());
+            }
+            other => panic!("expected a follow wave summary, got {:?}", other),
+        }
+        assert!(again.is_none());
+        assert_eq!(watch.observe(at(200), young()), Verdict::Notify);
+    }
+}
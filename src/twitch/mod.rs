@@ -1,9 +1,17 @@
 mod bits;
 pub mod eventsub;
 mod follower;
+pub mod follows;
 pub mod oauth;
+pub mod profiles;
+pub mod projection;
+mod raid;
+mod redemption;
+mod stream;
 mod subgift;
 mod subscriber;
+#[cfg(test)]
+pub mod testing;
 
 use std::sync::Arc;
 
@@ -59,6 +67,13 @@ pub async fn eventsub_register(
                 &state.env.twitch_broadcaster_id,
             ));
 
+        let resubscribe_exists = subs
+            .iter()
+            .any(subscriber::resubscribe::subscription_exists(
+                &state.env.twitch_eventsub_callback_url,
+                &state.env.twitch_broadcaster_id,
+            ));
+
         let subgift_exists = subs.iter().any(subgift::subscription_exists(
             &state.env.twitch_eventsub_callback_url,
             &state.env.twitch_broadcaster_id,
@@ -69,12 +84,43 @@ pub async fn eventsub_register(
             &state.env.twitch_broadcaster_id,
         ));
 
+        let raid_exists = subs.iter().any(raid::subscription_exists(
+            &state.env.twitch_eventsub_callback_url,
+            &state.env.twitch_broadcaster_id,
+        ));
+
+        let stream_online_exists = subs.iter().any(stream::online::subscription_exists(
+            &state.env.twitch_eventsub_callback_url,
+            &state.env.twitch_broadcaster_id,
+        ));
+
+        let stream_offline_exists = subs.iter().any(stream::offline::subscription_exists(
+            &state.env.twitch_eventsub_callback_url,
+            &state.env.twitch_broadcaster_id,
+        ));
+
+        let redemption_add_exists = subs.iter().any(redemption::add::subscription_exists(
+            &state.env.twitch_eventsub_callback_url,
+            &state.env.twitch_broadcaster_id,
+        ));
+
+        let redemption_update_exists = subs.iter().any(redemption::update::subscription_exists(
+            &state.env.twitch_eventsub_callback_url,
+            &state.env.twitch_broadcaster_id,
+        ));
+
         tracing::info!(
             follower = follower_exists,
             subscriber = subscribe_exists,
             subscriber_end = subscribe_end_exists,
+            resubscribe = resubscribe_exists,
             subgift = subgift_exists,
             bits = bits_exists,
+            raid = raid_exists,
+            stream_online = stream_online_exists,
+            stream_offline = stream_offline_exists,
+            redemption_add = redemption_add_exists,
+            redemption_update = redemption_update_exists,
             "existing subs"
         );
 
@@ -99,6 +145,45 @@ pub async fn eventsub_register(
             continue;
         }
 
+        if !raid_exists
+            && raid::create_subscription(
+                &state.env.twitch_broadcaster_id,
+                &token,
+                &helix,
+                &transport,
+            )
+            .await
+            .is_err()
+        {
+            continue;
+        }
+
+        if !stream_online_exists
+            && stream::online::create_subscription(
+                &state.env.twitch_broadcaster_id,
+                &token,
+                &helix,
+                &transport,
+            )
+            .await
+            .is_err()
+        {
+            continue;
+        }
+
+        if !stream_offline_exists
+            && stream::offline::create_subscription(
+                &state.env.twitch_broadcaster_id,
+                &token,
+                &helix,
+                &transport,
+            )
+            .await
+            .is_err()
+        {
+            continue;
+        }
+
         // can't register these events locally
         if !state.env.dev_mode {
             if !subscribe_exists
@@ -127,6 +212,19 @@ pub async fn eventsub_register(
                 continue;
             }
 
+            if !resubscribe_exists
+                && subscriber::resubscribe::create_subscription(
+                    &state.env.twitch_broadcaster_id,
+                    &token,
+                    &helix,
+                    &transport,
+                )
+                .await
+                .is_err()
+            {
+                continue;
+            }
+
             if !subgift_exists
                 && subgift::create_subscription(
                     &state.env.twitch_broadcaster_id,
@@ -152,6 +250,32 @@ pub async fn eventsub_register(
             {
                 continue;
             }
+
+            if !redemption_add_exists
+                && redemption::add::create_subscription(
+                    &state.env.twitch_broadcaster_id,
+                    &token,
+                    &helix,
+                    &transport,
+                )
+                .await
+                .is_err()
+            {
+                continue;
+            }
+
+            if !redemption_update_exists
+                && redemption::update::create_subscription(
+                    &state.env.twitch_broadcaster_id,
+                    &token,
+                    &helix,
+                    &transport,
+                )
+                .await
+                .is_err()
+            {
+                continue;
+            }
         }
     }
 
@@ -0,0 +1,209 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tables::{
+    timestamp,
+    user::{User, UserProfile},
+    OrmBase,
+};
+use tokio::sync::{oneshot, RwLock};
+use twitch_api::{helix, HelixClient};
+use twitch_oauth2::AppAccessToken;
+use twitch_types::{BroadcasterType, UserId};
+
+use crate::database::Database;
+
+const HELIX_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a profile is kept in memory, and how long a failed lookup is
+/// remembered before Helix is asked again.
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+const MISS_TTL: Duration = Duration::from_secs(5 * 60);
+/// Lookups made within `BATCH_WINDOW` of each other share a Helix request,
+/// which takes up to `BATCH_SIZE` ids, so a follow-bot wave costs a handful
+/// of calls rather than one per follower.
+const BATCH_WINDOW: Duration = Duration::from_millis(250);
+const BATCH_SIZE: usize = 100;
+
+type Lookup = (UserId, oneshot::Sender<Option<helix::users::User>>);
+
+/// `None` when Helix sends a creation date that can't be read.
+pub fn profile(user: &helix::users::User) -> Option<UserProfile> {
+    let broadcaster_type = match user.broadcaster_type {
+        Some(BroadcasterType::Partner) => "partner",
+        Some(BroadcasterType::Affiliate) => "affiliate",
+        _ => "",
+    };
+
+    Some(UserProfile {
+        login: user.login.to_string(),
+        account_created_at: timestamp::parse(user.created_at.as_str())?,
+        profile_image_url: user.profile_image_url.clone(),
+        broadcaster_type: broadcaster_type.to_string(),
+    })
+}
+
+/// Helix user profiles, by Twitch id. `get` goes through an in-memory cache,
+/// then the `users` table, and only then Helix.
+#[derive(Clone)]
+pub struct Profiles {
+    client: HelixClient<'static, reqwest::Client>,
+    token: Arc<RwLock<AppAccessToken>>,
+    database: Arc<Database>,
+    cache: Arc<retainer::Cache<String, Option<UserProfile>>>,
+    pending: Arc<Mutex<Vec<Lookup>>>,
+}
+
+impl Profiles {
+    pub fn new(
+        client: HelixClient<'static, reqwest::Client>,
+        token: Arc<RwLock<AppAccessToken>>,
+        database: Arc<Database>,
+    ) -> Self {
+        Self {
+            client,
+            token,
+            database,
+            cache: Arc::new(retainer::Cache::new()),
+            pending: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    /// Looks the user up on Helix. `None` when the lookup failed or the
+    /// account no longer exists.
+    ///
+    /// The first lookup queued becomes the one sending the batches, until
+    /// the queue is empty again.
+    pub async fn fetch(&self, twitch_id: &UserId) -> Option<helix::users::User> {
+        let (reply, user) = oneshot::channel();
+        let sends = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.push((twitch_id.clone(), reply));
+            pending.len() == 1
+        };
+
+        if sends {
+            tokio::time::sleep(BATCH_WINDOW).await;
+            loop {
+                let batch: Vec<Lookup> = {
+                    let mut pending = self.pending.lock().unwrap();
+                    let size = pending.len().min(BATCH_SIZE);
+                    pending.drain(..size).collect()
+                };
+                if batch.is_empty() {
+                    break;
+                }
+                self.lookup(batch).await;
+            }
+        }
+
+        user.await.ok().flatten()
+    }
+
+    async fn lookup(&self, batch: Vec<Lookup>) {
+        let ids: Vec<UserId> = batch.iter().map(|(id, _)| id.clone()).collect();
+        let token = self.token.read().await;
+        let request = helix::users::GetUsersRequest::ids(&ids[..]);
+
+        let users = match tokio::time::timeout(HELIX_TIMEOUT, self.client.req_get(request, &*token))
+            .await
+        {
+            Ok(Ok(response)) => response.data,
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to look up {} users: {}", ids.len(), e);
+                vec![]
+            }
+            Err(_) => {
+                tracing::warn!("Timed out looking up {} users", ids.len());
+                vec![]
+            }
+        };
+
+        for (id, reply) in batch {
+            let _ = reply.send(users.iter().find(|user| user.id == id).cloned());
+        }
+    }
+
+    async fn stored(&self, twitch_id: &str) -> Option<UserProfile> {
+        let twitch_id = twitch_id.parse::<u64>().ok()?;
+        let conn = self.database.conn().ok()?;
+
+        match User::get_by_twitch_id(&conn, twitch_id).await {
+            Ok(user) => user.and_then(|user| user.profile()),
+            Err(e) => {
+                tracing::error!("Failed to get user {}: {}", twitch_id, e);
+                None
+            }
+        }
+    }
+
+    pub async fn get(&self, twitch_id: &str) -> Option<UserProfile> {
+        if let Some(cached) = self.cache.get(&twitch_id.to_string()).await {
+            return cached.clone();
+        }
+
+        let profile = match self.stored(twitch_id).await {
+            Some(profile) => Some(profile),
+            None => self
+                .fetch(&UserId::from(twitch_id.to_string()))
+                .await
+                .and_then(|user| profile(&user)),
+        };
+        let ttl = match profile {
+            Some(_) => CACHE_TTL,
+            None => MISS_TTL,
+        };
+        self.cache
+            .insert(twitch_id.to_string(), profile.clone(), ttl)
+            .await;
+
+        profile
+    }
+
+    /// Stores the Helix profile of a user that does not have one yet. Returns
+    /// the user as stored, with or without a profile depending on the lookup.
+    pub async fn enrich(&self, conn: &libsql::Connection, id: u64) -> Option<User> {
+        let user = match User::get(conn, id).await {
+            Ok(Some(user)) => user,
+            Ok(None) => return None,
+            Err(e) => {
+                tracing::error!("Failed to get user {}: {}", id, e);
+                return None;
+            }
+        };
+
+        if user.enriched_at.is_some() {
+            return Some(user);
+        }
+
+        let twitch_id = user.twitch_id.to_string();
+        let Some(profile) = self
+            .fetch(&UserId::from(twitch_id.clone()))
+            .await
+            .and_then(|helix_user| profile(&helix_user))
+        else {
+            return Some(user);
+        };
+
+        if let Err(e) = User::set_profile(conn, id, &profile).await {
+            tracing::error!("Failed to store the profile of user {}: {}", id, e);
+            return Some(user);
+        }
+        self.cache.insert(twitch_id, Some(profile), CACHE_TTL).await;
+
+        match User::get(conn, id).await {
+            Ok(Some(enriched)) => Some(enriched),
+            _ => Some(user),
+        }
+    }
+
+    /// Purges expired profiles until the process stops.
+    pub async fn monitor(self) -> Result<(), eyre::Report> {
+        self.cache
+            .monitor(10, 0.50, tokio::time::Duration::from_secs(60 * 60))
+            .await;
+
+        Ok(())
+    }
+}
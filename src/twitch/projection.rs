@@ -0,0 +1,600 @@
+use chrono::{DateTime, Utc};
+use twitch_api::eventsub::{
+    channel::{
+        channel_points_custom_reward_redemption::RedemptionStatus, ChannelCheerV1Payload,
+        ChannelFollowV2Payload, ChannelPointsCustomRewardRedemptionAddV1Payload,
+        ChannelPointsCustomRewardRedemptionUpdateV1Payload, ChannelRaidV1Payload,
+        ChannelSubscribeV1Payload, ChannelSubscriptionEndV1Payload,
+        ChannelSubscriptionGiftV1Payload, ChannelSubscriptionMessageV1Payload,
+    },
+    stream::{StreamOfflineV1Payload, StreamOnlineV1Payload},
+    Event, Message as M, Payload as P,
+};
+use twitch_types::{DisplayName, UserId};
+
+use crate::models::sub_tier::SubTier;
+use tables::{
+    timestamp,
+    user::{User, UserPatch},
+    OrmBase, OrmError, TwitchId,
+};
+
+/// What applying a notification to the derived tables touched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Applied {
+    Nothing,
+    Anonymous,
+    User(u64),
+    Stream(u64),
+}
+
+/// When a notification happened and the stream that was live at the time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context {
+    pub at: DateTime<Utc>,
+    pub stream_id: Option<u64>,
+}
+
+pub fn username(is_anonymous: bool, user_name: &Option<DisplayName>) -> String {
+    match (is_anonymous, user_name) {
+        (false, Some(user_name)) => user_name.to_string(),
+        _ => "Anonymous".to_string(),
+    }
+}
+
+pub fn redemption_status(status: &RedemptionStatus) -> &'static str {
+    match status {
+        RedemptionStatus::Unfulfilled => "unfulfilled",
+        RedemptionStatus::Fulfilled => "fulfilled",
+        RedemptionStatus::Canceled => "canceled",
+        _ => "unknown",
+    }
+}
+
+fn twitch_id(user_id: &UserId) -> u64 {
+    let twitch_id: TwitchId = user_id.clone().into();
+    twitch_id.0
+}
+
+/// Updates `users`, `streams` and the per-event tables (and through their
+/// triggers `latests`) for a notification. `at` is the journaled message
+/// timestamp, used for every timestamp written so that a replay of the
+/// journal reproduces the original times.
+/// Rows are tagged with the stream that is active when they are applied.
+pub async fn apply(
+    conn: &libsql::Connection,
+    event: &Event,
+    at: DateTime<Utc>,
+) -> Result<Applied, OrmError> {
+    let ctx = Context {
+        at,
+        stream_id: tables::streams::Stream::active(conn)
+            .await?
+            .map(|stream| stream.id),
+    };
+
+    match event {
+        Event::ChannelFollowV2(P {
+            message:
+                M::Notification(ChannelFollowV2Payload {
+                    user_name, user_id, ..
+                }),
+            ..
+        }) => follow(conn, twitch_id(user_id), user_name.to_string(), &ctx).await,
+        Event::ChannelSubscribeV1(P {
+            message:
+                M::Notification(ChannelSubscribeV1Payload {
+                    tier,
+                    user_id,
+                    user_name,
+                    ..
+                }),
+            ..
+        }) => {
+            subscribe(
+                conn,
+                twitch_id(user_id),
+                user_name.to_string(),
+                &SubTier::from(tier.clone()),
+                &ctx,
+            )
+            .await
+        }
+        Event::ChannelSubscriptionEndV1(P {
+            message:
+                M::Notification(ChannelSubscriptionEndV1Payload {
+                    user_id, user_name, ..
+                }),
+            ..
+        }) => subscribe_end(conn, twitch_id(user_id), user_name.to_string(), &ctx).await,
+        Event::ChannelSubscriptionMessageV1(P {
+            message:
+                M::Notification(ChannelSubscriptionMessageV1Payload {
+                    user_id,
+                    user_name,
+                    tier,
+                    cumulative_months,
+                    streak_months,
+                    duration_months,
+                    message,
+                    ..
+                }),
+            ..
+        }) => {
+            let tier = SubTier::from(tier.clone());
+            let mut resub = tables::resubs::Resub::from(
+                0,
+                tier.to_string(),
+                (*cumulative_months).max(0) as u32,
+                (*duration_months).max(0) as u32,
+            );
+            resub.streak_months = streak_months.map(|v| v.max(0) as u32);
+            resub.message = Some(message.text.clone()).filter(|text| !text.is_empty());
+
+            resubscribe(
+                conn,
+                twitch_id(user_id),
+                user_name.to_string(),
+                &tier,
+                resub,
+                &ctx,
+            )
+            .await
+        }
+        Event::ChannelSubscriptionGiftV1(P {
+            message:
+                M::Notification(ChannelSubscriptionGiftV1Payload {
+                    tier,
+                    is_anonymous,
+                    total,
+                    user_id,
+                    user_name,
+                    ..
+                }),
+            ..
+        }) => {
+            subgift(
+                conn,
+                user_id.as_ref().map(twitch_id),
+                username(*is_anonymous, user_name),
+                (*total).max(0) as u16,
+                &SubTier::from(tier.clone()),
+                *is_anonymous,
+                &ctx,
+            )
+            .await
+        }
+        Event::ChannelCheerV1(P {
+            message:
+                M::Notification(ChannelCheerV1Payload {
+                    user_id,
+                    user_name,
+                    bits,
+                    message,
+                    is_anonymous,
+                    ..
+                }),
+            ..
+        }) => {
+            cheer(
+                conn,
+                user_id.as_ref().map(twitch_id),
+                username(*is_anonymous, user_name),
+                (*bits).max(0) as u32,
+                message.clone(),
+                *is_anonymous,
+                &ctx,
+            )
+            .await
+        }
+        Event::ChannelPointsCustomRewardRedemptionAddV1(P {
+            message:
+                M::Notification(ChannelPointsCustomRewardRedemptionAddV1Payload {
+                    id,
+                    user_id,
+                    user_name,
+                    reward,
+                    user_input,
+                    status,
+                    ..
+                }),
+            ..
+        }) => {
+            let mut redemption = tables::redemptions::Redemption::from(
+                0,
+                id.to_string(),
+                reward.id.to_string(),
+                reward.title.clone(),
+                reward.cost.max(0) as u64,
+            );
+            redemption.user_input = Some(user_input.clone()).filter(|input| !input.is_empty());
+            redemption.status = redemption_status(status).to_string();
+
+            redeem(
+                conn,
+                twitch_id(user_id),
+                user_name.to_string(),
+                redemption,
+                &ctx,
+            )
+            .await
+        }
+        Event::ChannelPointsCustomRewardRedemptionUpdateV1(P {
+            message:
+                M::Notification(ChannelPointsCustomRewardRedemptionUpdateV1Payload {
+                    id,
+                    user_id,
+                    user_name,
+                    reward,
+                    user_input,
+                    status,
+                    ..
+                }),
+            ..
+        }) => {
+            let mut redemption = tables::redemptions::Redemption::from(
+                0,
+                id.to_string(),
+                reward.id.to_string(),
+                reward.title.clone(),
+                reward.cost.max(0) as u64,
+            );
+            redemption.user_input = Some(user_input.clone()).filter(|input| !input.is_empty());
+            redemption.status = redemption_status(status).to_string();
+
+            redemption_update(
+                conn,
+                twitch_id(user_id),
+                user_name.to_string(),
+                redemption,
+                &ctx,
+            )
+            .await
+        }
+        Event::ChannelRaidV1(P {
+            message:
+                M::Notification(ChannelRaidV1Payload {
+                    from_broadcaster_user_id,
+                    from_broadcaster_user_name,
+                    viewers,
+                    ..
+                }),
+            ..
+        }) => {
+            raid(
+                conn,
+                twitch_id(from_broadcaster_user_id),
+                from_broadcaster_user_name.to_string(),
+                (*viewers).max(0) as u32,
+                &ctx,
+            )
+            .await
+        }
+        Event::StreamOnlineV1(P {
+            message:
+                M::Notification(StreamOnlineV1Payload {
+                    id,
+                    type_,
+                    started_at,
+                    ..
+                }),
+            ..
+        }) => {
+            let stream_type = serde_json::to_value(type_)
+                .ok()
+                .and_then(|v| v.as_str().map(String::from))
+                .unwrap_or("live".to_string());
+            let started_at = timestamp::parse(started_at.as_str()).unwrap_or(ctx.at);
+
+            stream_online(conn, id.clone(), stream_type, started_at).await
+        }
+        Event::StreamOfflineV1(P {
+            message: M::Notification(StreamOfflineV1Payload { .. }),
+            ..
+        }) => stream_offline(conn, &ctx).await,
+        _ => Ok(Applied::Nothing),
+    }
+}
+
+pub async fn follow(
+    conn: &libsql::Connection,
+    twitch_id: u64,
+    user_name: String,
+    ctx: &Context,
+) -> Result<Applied, OrmError> {
+    let patch = UserPatch::follow(ctx.at);
+    let user_id = User::upsert_by_twitch_id(conn, twitch_id, &user_name, &patch, &ctx.at).await?;
+
+    Ok(Applied::User(user_id))
+}
+
+pub async fn subscribe(
+    conn: &libsql::Connection,
+    twitch_id: u64,
+    user_name: String,
+    tier: &SubTier,
+    ctx: &Context,
+) -> Result<Applied, OrmError> {
+    let patch = UserPatch::subscribe(ctx.at, tier.to_string());
+    let user_id = User::upsert_by_twitch_id(conn, twitch_id, &user_name, &patch, &ctx.at).await?;
+
+    Ok(Applied::User(user_id))
+}
+
+/// Resubs update the user's tier but keep the date the subscription started,
+/// which is only set when it was never recorded (e.g. subscribed before the
+/// journal existed).
+pub async fn resubscribe(
+    conn: &libsql::Connection,
+    twitch_id: u64,
+    user_name: String,
+    tier: &SubTier,
+    mut resub: tables::resubs::Resub,
+    ctx: &Context,
+) -> Result<Applied, OrmError> {
+    let patch = UserPatch::resubscribe(ctx.at, tier.to_string());
+    let user_id = User::upsert_by_twitch_id(conn, twitch_id, &user_name, &patch, &ctx.at).await?;
+
+    resub.user_id = Some(user_id);
+    resub.created_at = ctx.at;
+    resub.stream_id = ctx.stream_id;
+
+    resub.create(conn).await?;
+
+    Ok(Applied::User(user_id))
+}
+
+pub async fn subscribe_end(
+    conn: &libsql::Connection,
+    twitch_id: u64,
+    user_name: String,
+    ctx: &Context,
+) -> Result<Applied, OrmError> {
+    let patch = UserPatch::unsubscribe();
+    let user_id = User::upsert_by_twitch_id(conn, twitch_id, &user_name, &patch, &ctx.at).await?;
+
+    Ok(Applied::User(user_id))
+}
+
+pub async fn subgift(
+    conn: &libsql::Connection,
+    twitch_id: Option<u64>,
+    username: String,
+    total: u16,
+    tier: &SubTier,
+    is_anonymous: bool,
+    ctx: &Context,
+) -> Result<Applied, OrmError> {
+    if is_anonymous {
+        let mut subgift = tables::subgifts::Subgift::from_anonymous(total, tier.to_string());
+        subgift.created_at = ctx.at;
+        subgift.stream_id = ctx.stream_id;
+
+        subgift.create(conn).await?;
+
+        return Ok(Applied::Anonymous);
+    }
+
+    let twitch_id = twitch_id.ok_or(OrmError::BadInput(
+        "a twitch_id for non anonymous user".to_string(),
+    ))?;
+    let user_id =
+        User::upsert_by_twitch_id(conn, twitch_id, &username, &UserPatch::default(), &ctx.at)
+            .await?;
+
+    let mut subgift = tables::subgifts::Subgift::from(user_id, total, tier.to_string());
+    subgift.created_at = ctx.at;
+    subgift.stream_id = ctx.stream_id;
+
+    subgift.create(conn).await?;
+
+    Ok(Applied::User(user_id))
+}
+
+pub async fn cheer(
+    conn: &libsql::Connection,
+    twitch_id: Option<u64>,
+    username: String,
+    number: u32,
+    message: String,
+    is_anonymous: bool,
+    ctx: &Context,
+) -> Result<Applied, OrmError> {
+    if is_anonymous {
+        let mut bits = tables::bits::Bit::from_anonymous(number, Some(message));
+        bits.created_at = ctx.at;
+        bits.stream_id = ctx.stream_id;
+
+        bits.create(conn).await?;
+
+        return Ok(Applied::Anonymous);
+    }
+
+    let twitch_id = twitch_id.ok_or(OrmError::BadInput(
+        "a twitch_id for non anonymous user".to_string(),
+    ))?;
+    let user_id =
+        User::upsert_by_twitch_id(conn, twitch_id, &username, &UserPatch::default(), &ctx.at)
+            .await?;
+
+    let mut bits = tables::bits::Bit::from(user_id, number, Some(message));
+    bits.created_at = ctx.at;
+    bits.stream_id = ctx.stream_id;
+
+    bits.create(conn).await?;
+
+    Ok(Applied::User(user_id))
+}
+
+pub async fn raid(
+    conn: &libsql::Connection,
+    twitch_id: u64,
+    user_name: String,
+    viewers: u32,
+    ctx: &Context,
+) -> Result<Applied, OrmError> {
+    let user_id =
+        User::upsert_by_twitch_id(conn, twitch_id, &user_name, &UserPatch::default(), &ctx.at)
+            .await?;
+
+    let mut raid = tables::raids::Raid::from(user_id, viewers);
+    raid.created_at = ctx.at;
+    raid.stream_id = ctx.stream_id;
+
+    raid.create(conn).await?;
+
+    Ok(Applied::User(user_id))
+}
+
+async fn redeemer(
+    conn: &libsql::Connection,
+    twitch_id: u64,
+    user_name: String,
+    at: &DateTime<Utc>,
+) -> Result<u64, OrmError> {
+    User::upsert_by_twitch_id(conn, twitch_id, &user_name, &UserPatch::default(), at).await
+}
+
+pub async fn redeem(
+    conn: &libsql::Connection,
+    twitch_id: u64,
+    user_name: String,
+    mut redemption: tables::redemptions::Redemption,
+    ctx: &Context,
+) -> Result<Applied, OrmError> {
+    let user_id = redeemer(conn, twitch_id, user_name, &ctx.at).await?;
+
+    redemption.user_id = Some(user_id);
+    redemption.created_at = ctx.at;
+    redemption.stream_id = ctx.stream_id;
+
+    redemption.create(conn).await?;
+
+    Ok(Applied::User(user_id))
+}
+
+/// Records the redemption if its `.add` notification was missed.
+pub async fn redemption_update(
+    conn: &libsql::Connection,
+    twitch_id: u64,
+    user_name: String,
+    redemption: tables::redemptions::Redemption,
+    ctx: &Context,
+) -> Result<Applied, OrmError> {
+    let existing =
+        tables::redemptions::Redemption::get_by_redemption_id(conn, &redemption.redemption_id)
+            .await?;
+
+    match existing {
+        None => redeem(conn, twitch_id, user_name, redemption, ctx).await,
+        Some(existing) => {
+            tables::redemptions::Redemption::update_status(
+                conn,
+                &existing.redemption_id,
+                &redemption.status,
+                ctx.at,
+            )
+            .await?;
+
+            Ok(match existing.user_id {
+                Some(user_id) => Applied::User(user_id),
+                None => Applied::Anonymous,
+            })
+        }
+    }
+}
+
+/// A missed `stream.offline` must not leave the previous stream open forever,
+/// so any active stream is closed when a new one starts.
+pub async fn stream_online(
+    conn: &libsql::Connection,
+    stream_id: String,
+    stream_type: String,
+    started_at: DateTime<Utc>,
+) -> Result<Applied, OrmError> {
+    tables::streams::Stream::end(conn, started_at).await?;
+
+    let stream = tables::streams::Stream::from(stream_id, stream_type, started_at);
+
+    Ok(Applied::Stream(stream.create(conn).await?))
+}
+
+pub async fn stream_offline(conn: &libsql::Connection, ctx: &Context) -> Result<Applied, OrmError> {
+    match tables::streams::Stream::end(conn, ctx.at).await? {
+        None => {
+            tracing::warn!("got stream offline event without an active stream");
+            Ok(Applied::Nothing)
+        }
+        Some(stream) => Ok(Applied::Stream(stream.id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tables::{bits::Bit, latests::Latests};
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::{
+        database::Database,
+        twitch::testing::{cheer, follow, parse},
+    };
+
+    fn at() -> DateTime<Utc> {
+        timestamp::parse("2025-02-10T20:00:00.000Z").unwrap()
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn apply_follow() {
+        // arrange
+        let conn = Database::scratch().await.conn().unwrap();
+        let event = parse(&follow(1234, "arinono"));
+
+        // act
+        let res = apply(&conn, &event, at()).await;
+
+        // assert
+        let user = User::get_by_twitch_id(&conn, 1234).await.unwrap().unwrap();
+        assert_eq!(res.unwrap(), Applied::User(user.id));
+        assert_eq!(user.display_name, "arinono");
+        assert_eq!(user.follower_since, Some(at()));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn apply_cheer() {
+        // arrange
+        let conn = Database::scratch().await.conn().unwrap();
+        let event = parse(&cheer(Some(1234), "arinono", 100));
+
+        // act
+        let res = apply(&conn, &event, at()).await;
+
+        // assert
+        let user = User::get_by_twitch_id(&conn, 1234).await.unwrap().unwrap();
+        assert_eq!(res.unwrap(), Applied::User(user.id));
+        let bits = Bit::list(&conn, 10, 0).await.unwrap();
+        assert_eq!(bits.len(), 1);
+        assert_eq!(bits[0].user_id, Some(user.id));
+        assert_eq!(bits[0].number, 100);
+        assert_eq!(bits[0].created_at, at());
+        let latest = Latests::get_latest_bit(&conn).await.unwrap().unwrap();
+        assert_eq!(latest.name, "arinono");
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn apply_cheer_without_user() {
+        // arrange
+        let conn = Database::scratch().await.conn().unwrap();
+        let event = parse(&cheer(None, "arinono", 100));
+
+        // act
+        let res = apply(&conn, &event, at()).await;
+
+        // assert
+        assert!(matches!(res, Err(OrmError::BadInput(_))));
+        assert!(Bit::list(&conn, 10, 0).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+use eyre::eyre;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use twitch_api::{
+    eventsub::{channel::ChannelRaidV1, EventSubSubscription, EventType, Transport},
+    HelixClient,
+};
+use twitch_oauth2::AppAccessToken;
+
+pub fn subscription_exists<'a>(
+    eventsub_callback_url: &'a str,
+    broadcaster_id: &'a str,
+) -> impl 'a + FnMut(&EventSubSubscription) -> bool {
+    move |sub: &EventSubSubscription| {
+        sub.transport.as_webhook().expect("webhook").callback == eventsub_callback_url
+            && sub.version == "1"
+            && sub.type_ == EventType::ChannelRaid
+            && sub
+                .condition
+                .as_object()
+                .expect("channel.raid does not contain an object")
+                .get("to_broadcaster_user_id")
+                .expect("channel.raid does not contain to_broadcaster_user_id")
+                .as_str()
+                == Some(broadcaster_id)
+    }
+}
+
+pub async fn create_subscription<'a>(
+    broadcaster_id: &'a str,
+    token: &'a Arc<RwLock<AppAccessToken>>,
+    helix: &'a HelixClient<'static, reqwest::Client>,
+    transport: &'a Transport,
+) -> Result<(), eyre::Report> {
+    tracing::info!("Creating new subscription");
+
+    match helix
+        .create_eventsub_subscription(
+            ChannelRaidV1::to_broadcaster_user_id(broadcaster_id),
+            transport.clone(),
+            &*token.read().await,
+        )
+        .await
+    {
+        Ok(sub) => {
+            tracing::info!("Created subscription: {:#?}", sub);
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!("Failed to create subscription: {:#?}", e);
+            Err(eyre!(e))
+        }
+    }
+}
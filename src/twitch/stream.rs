@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use eyre::eyre;
+use tokio::sync::RwLock;
+use twitch_api::eventsub::{EventSubSubscription, EventType};
+use twitch_api::{eventsub::Transport, HelixClient};
+use twitch_oauth2::AppAccessToken;
+
+pub mod online {
+    use twitch_api::eventsub::stream::StreamOnlineV1;
+
+    use super::*;
+
+    pub fn subscription_exists<'a>(
+        eventsub_callback_url: &'a str,
+        broadcaster_id: &'a str,
+    ) -> impl 'a + FnMut(&EventSubSubscription) -> bool {
+        move |sub: &EventSubSubscription| {
+            sub.transport.as_webhook().expect("webhook").callback == eventsub_callback_url
+                && sub.version == "1"
+                && sub.type_ == EventType::StreamOnline
+                && sub
+                    .condition
+                    .as_object()
+                    .expect("stream.online does not contain an object")
+                    .get("broadcaster_user_id")
+                    .expect("stream.online does not contain broadcaster_user_id")
+                    .as_str()
+                    == Some(broadcaster_id)
+        }
+    }
+
+    pub async fn create_subscription<'a>(
+        broadcaster_id: &'a str,
+        token: &'a Arc<RwLock<AppAccessToken>>,
+        helix: &'a HelixClient<'static, reqwest::Client>,
+        transport: &'a Transport,
+    ) -> Result<(), eyre::Report> {
+        tracing::info!("Creating new subscription");
+        match helix
+            .create_eventsub_subscription(
+                StreamOnlineV1::broadcaster_user_id(broadcaster_id),
+                transport.clone(),
+                &*token.read().await,
+            )
+            .await
+        {
+            Ok(sub) => {
+                tracing::info!("Created subscription: {:#?}", sub);
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!("Failed to create subscription: {:#?}", e);
+                Err(eyre!(e))
+            }
+        }
+    }
+}
+
+pub mod offline {
+    use twitch_api::eventsub::stream::StreamOfflineV1;
+
+    use super::*;
+
+    pub fn subscription_exists<'a>(
+        eventsub_callback_url: &'a str,
+        broadcaster_id: &'a str,
+    ) -> impl 'a + FnMut(&EventSubSubscription) -> bool {
+        move |sub: &EventSubSubscription| {
+            sub.transport.as_webhook().expect("webhook").callback == eventsub_callback_url
+                && sub.version == "1"
+                && sub.type_ == EventType::StreamOffline
+                && sub
+                    .condition
+                    .as_object()
+                    .expect("stream.offline does not contain an object")
+                    .get("broadcaster_user_id")
+                    .expect("stream.offline does not contain broadcaster_user_id")
+                    .as_str()
+                    == Some(broadcaster_id)
+        }
+    }
+
+    pub async fn create_subscription<'a>(
+        broadcaster_id: &'a str,
+        token: &'a Arc<RwLock<AppAccessToken>>,
+        helix: &'a HelixClient<'static, reqwest::Client>,
+        transport: &'a Transport,
+    ) -> Result<(), eyre::Report> {
+        tracing::info!("Creating new subscription");
+        match helix
+            .create_eventsub_subscription(
+                StreamOfflineV1::broadcaster_user_id(broadcaster_id),
+                transport.clone(),
+                &*token.read().await,
+            )
+            .await
+        {
+            Ok(sub) => {
+                tracing::info!("Created subscription: {:#?}", sub);
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!("Failed to create subscription: {:#?}", e);
+                Err(eyre!(e))
+            }
+        }
+    }
+}
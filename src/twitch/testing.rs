@@ -0,0 +1,68 @@
+//! EventSub notifications for the tests, as Twitch sends them.
+
+use serde_json::{json, Value};
+use twitch_api::eventsub::Event;
+
+/// The raw body of a `kind` notification, as the journal stores it.
+pub fn notification(kind: &str, version: &str, event: Value) -> String {
+    json!({
+        "subscription": {
+            "id": "f1c2a387-161a-49f9-a165-0f21d7a4e1c4",
+            "status": "enabled",
+            "type": kind,
+            "version": version,
+            "cost": 0,
+            "condition": {
+                "broadcaster_user_id": "1337",
+                "moderator_user_id": "1337",
+            },
+            "transport": {
+                "method": "webhook",
+                "callback": "https://example.com/webhooks/callback",
+            },
+            "created_at": "2025-02-10T19:00:00.000Z",
+        },
+        "event": event,
+    })
+    .to_string()
+}
+
+pub fn follow(twitch_id: u64, username: &str) -> String {
+    notification(
+        "channel.follow",
+        "2",
+        json!({
+            "user_id": twitch_id.to_string(),
+            "user_login": username.to_lowercase(),
+            "user_name": username,
+            "broadcaster_user_id": "1337",
+            "broadcaster_user_login": "arinono",
+            "broadcaster_user_name": "arinono",
+            "followed_at": "2025-02-10T20:00:00.000Z",
+        }),
+    )
+}
+
+/// A cheer of `twitch_id`, or one that names no user although it is not
+/// anonymous when `twitch_id` is `None`.
+pub fn cheer(twitch_id: Option<u64>, username: &str, bits: u32) -> String {
+    notification(
+        "channel.cheer",
+        "1",
+        json!({
+            "is_anonymous": false,
+            "user_id": twitch_id.map(|id| id.to_string()),
+            "user_login": twitch_id.map(|_| username.to_lowercase()),
+            "user_name": twitch_id.map(|_| username),
+            "broadcaster_user_id": "1337",
+            "broadcaster_user_login": "arinono",
+            "broadcaster_user_name": "arinono",
+            "message": "Cheer100",
+            "bits": bits,
+        }),
+    )
+}
+
+pub fn parse(raw: &str) -> Event {
+    Event::parse(raw).unwrap()
+}